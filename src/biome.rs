@@ -0,0 +1,281 @@
+use noise::{NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+/// Horizontal frequency of the temperature and humidity fields, per meter.
+const CLIMATE_FREQUENCY: f64 = 0.0045;
+/// Climate distance over which a biome's influence fades out; larger values blend wider borders.
+const BIOME_BLEND_RADIUS: f32 = 0.22;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Mountains,
+    Tundra,
+}
+
+/// Blocks a biome covers its columns with.
+#[derive(Clone, Copy, Debug)]
+pub struct BiomeTerrain {
+    /// Block name of the topmost voxel of a column.
+    pub surface: &'static str,
+    /// Block name of the layer between the surface and stone.
+    pub subsurface: &'static str,
+}
+
+/// Terrain shape a biome contributes to the columns it covers, weighted by its influence.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct BiomeShape {
+    pub base_height_meters: f32,
+    pub primary_variation_meters: f32,
+    pub secondary_variation_meters: f32,
+    pub detail_variation_meters: f32,
+    /// Thickness of the layer between the surface and stone, such as the dirt under grass.
+    pub subsurface_depth_meters: f32,
+}
+
+/// Shape of every biome, saved with a world's terrain settings like the rest of its tunables.
+/// Fields missing from the saved form keep the defaults of their own biome.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedBiomeSettings")]
+pub struct BiomeSettings {
+    pub plains: BiomeShape,
+    pub desert: BiomeShape,
+    pub mountains: BiomeShape,
+    pub tundra: BiomeShape,
+}
+
+impl Default for BiomeSettings {
+    fn default() -> Self {
+        Self {
+            plains: BiomeShape {
+                base_height_meters: 5.6,
+                primary_variation_meters: 1.4,
+                secondary_variation_meters: 0.6,
+                detail_variation_meters: 0.25,
+                subsurface_depth_meters: 0.7,
+            },
+            desert: BiomeShape {
+                base_height_meters: 5.2,
+                primary_variation_meters: 0.8,
+                secondary_variation_meters: 0.9,
+                detail_variation_meters: 0.15,
+                subsurface_depth_meters: 1.2,
+            },
+            mountains: BiomeShape {
+                base_height_meters: 7.4,
+                primary_variation_meters: 2.6,
+                secondary_variation_meters: 1.2,
+                detail_variation_meters: 0.5,
+                subsurface_depth_meters: 0.3,
+            },
+            tundra: BiomeShape {
+                base_height_meters: 6.0,
+                primary_variation_meters: 1.8,
+                secondary_variation_meters: 0.7,
+                detail_variation_meters: 0.3,
+                subsurface_depth_meters: 0.5,
+            },
+        }
+    }
+}
+
+impl BiomeSettings {
+    pub fn shape(&self, biome: Biome) -> &BiomeShape {
+        match biome {
+            Biome::Plains => &self.plains,
+            Biome::Desert => &self.desert,
+            Biome::Mountains => &self.mountains,
+            Biome::Tundra => &self.tundra,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct SavedBiomeSettings {
+    plains: SavedBiomeShape,
+    desert: SavedBiomeShape,
+    mountains: SavedBiomeShape,
+    tundra: SavedBiomeShape,
+}
+
+impl From<SavedBiomeSettings> for BiomeSettings {
+    fn from(saved: SavedBiomeSettings) -> Self {
+        let defaults = Self::default();
+        Self {
+            plains: saved.plains.or(defaults.plains),
+            desert: saved.desert.or(defaults.desert),
+            mountains: saved.mountains.or(defaults.mountains),
+            tundra: saved.tundra.or(defaults.tundra),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct SavedBiomeShape {
+    base_height_meters: Option<f32>,
+    primary_variation_meters: Option<f32>,
+    secondary_variation_meters: Option<f32>,
+    detail_variation_meters: Option<f32>,
+    subsurface_depth_meters: Option<f32>,
+}
+
+impl SavedBiomeShape {
+    fn or(self, defaults: BiomeShape) -> BiomeShape {
+        BiomeShape {
+            base_height_meters: self
+                .base_height_meters
+                .unwrap_or(defaults.base_height_meters),
+            primary_variation_meters: self
+                .primary_variation_meters
+                .unwrap_or(defaults.primary_variation_meters),
+            secondary_variation_meters: self
+                .secondary_variation_meters
+                .unwrap_or(defaults.secondary_variation_meters),
+            detail_variation_meters: self
+                .detail_variation_meters
+                .unwrap_or(defaults.detail_variation_meters),
+            subsurface_depth_meters: self
+                .subsurface_depth_meters
+                .unwrap_or(defaults.subsurface_depth_meters),
+        }
+    }
+}
+
+impl Biome {
+    pub const ALL: [Biome; 4] = [
+        Biome::Plains,
+        Biome::Desert,
+        Biome::Mountains,
+        Biome::Tundra,
+    ];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Biome::Plains => "Plains",
+            Biome::Desert => "Desert",
+            Biome::Mountains => "Mountains",
+            Biome::Tundra => "Tundra",
+        }
+    }
+
+    /// Temperature and humidity this biome is centered on, in the range of the climate noise.
+    fn climate(self) -> (f32, f32) {
+        match self {
+            Biome::Plains => (0.0, 0.1),
+            Biome::Desert => (0.45, -0.35),
+            Biome::Mountains => (-0.1, 0.45),
+            Biome::Tundra => (-0.45, -0.2),
+        }
+    }
+
+    pub fn terrain(self) -> BiomeTerrain {
+        match self {
+            Biome::Plains => BiomeTerrain {
+                surface: "grass",
+                subsurface: "dirt",
+            },
+            Biome::Desert => BiomeTerrain {
+                surface: "sand",
+                subsurface: "sand",
+            },
+            Biome::Mountains => BiomeTerrain {
+                surface: "stone",
+                subsurface: "stone",
+            },
+            Biome::Tundra => BiomeTerrain {
+                surface: "snow",
+                subsurface: "dirt",
+            },
+        }
+    }
+}
+
+/// Temperature and humidity noise fields that pick the biomes of every column.
+pub struct BiomeMap {
+    temperature: Perlin,
+    humidity: Perlin,
+}
+
+impl BiomeMap {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            temperature: Perlin::new(seed.wrapping_add(31337)),
+            humidity: Perlin::new(seed.wrapping_add(73313)),
+        }
+    }
+
+    /// Temperature and humidity of the column at world `x`/`z`.
+    pub fn climate(&self, x: f64, z: f64) -> (f32, f32) {
+        let point = [x * CLIMATE_FREQUENCY, z * CLIMATE_FREQUENCY];
+        (
+            self.temperature.get(point) as f32,
+            self.humidity.get(point) as f32,
+        )
+    }
+
+    /// Influence of every biome in `Biome::ALL` on a column. The weights sum to one and change
+    /// smoothly with position, so blending per-biome parameters leaves no seams at borders.
+    pub fn weights(&self, x: f64, z: f64) -> [f32; Biome::ALL.len()] {
+        let (temperature, humidity) = self.climate(x, z);
+        let mut weights = Biome::ALL.map(|biome| {
+            let (center_temperature, center_humidity) = biome.climate();
+            let distance_squared =
+                (temperature - center_temperature).powi(2) + (humidity - center_humidity).powi(2);
+            (-distance_squared / (BIOME_BLEND_RADIUS * BIOME_BLEND_RADIUS)).exp()
+        });
+
+        let total = weights.iter().sum::<f32>();
+        for weight in &mut weights {
+            *weight /= total;
+        }
+        weights
+    }
+
+    /// Biome with the most influence on the column at world `x`/`z`.
+    pub fn biome_at(&self, x: f64, z: f64) -> Biome {
+        dominant_biome(&self.weights(x, z))
+    }
+}
+
+pub fn dominant_biome(weights: &[f32; Biome::ALL.len()]) -> Biome {
+    Biome::ALL
+        .into_iter()
+        .zip(weights.iter().copied())
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(biome, _)| biome)
+        .unwrap_or(Biome::Plains)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_are_normalized_and_every_biome_occurs() {
+        let map = BiomeMap::new(7);
+        let mut seen = Vec::new();
+
+        for x in (-3000..3000).step_by(20) {
+            for z in (-3000..3000).step_by(20) {
+                let weights = map.weights(x as f64, z as f64);
+                assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+
+                let biome = dominant_biome(&weights);
+                if !seen.contains(&biome) {
+                    seen.push(biome);
+                }
+
+                // A small step never flips the blend abruptly.
+                let next = map.weights(x as f64 + 0.0625, z as f64);
+                for (a, b) in weights.iter().zip(next) {
+                    assert!((a - b).abs() < 0.05);
+                }
+            }
+        }
+
+        assert_eq!(seen.len(), Biome::ALL.len());
+    }
+}
@@ -0,0 +1,450 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::biome::Biome;
+use crate::light::MAX_LIGHT_LEVEL;
+use crate::ore::OreSettings;
+use crate::voxel::{BlockId, Voxel};
+
+pub const BLOCK_DEFINITIONS_PATH: &str = "assets/blocks.json";
+const DEFAULT_BLOCK_DEFINITIONS: &str = include_str!("../assets/blocks.json");
+/// Blocks the world generators place by name, besides the surface and subsurface of every biome
+/// and the ores.
+const GENERATED_BLOCK_NAMES: [&str; 8] = [
+    "stone", "dirt", "grass", "sand", "gravel", "water", "log", "leaves",
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockDefinition {
+    pub id: u16,
+    pub name: String,
+    pub display_name: String,
+    pub color: [f32; 4],
+    pub solid: bool,
+    pub opaque: bool,
+    pub hardness: f32,
+    #[serde(default)]
+    pub hotbar_slot: Option<u8>,
+    #[serde(default)]
+    pub properties: Vec<BlockProperty>,
+    #[serde(default)]
+    pub fluid: Option<FluidDefinition>,
+    /// Drops until it lands when the voxel below stops supporting it.
+    #[serde(default)]
+    pub falls: bool,
+    /// Block light level the block emits, up to `MAX_LIGHT_LEVEL`.
+    #[serde(default)]
+    pub light_emission: u8,
+}
+
+/// Flow behaviour of a fluid block. Fluids also declare a `level` property whose first value is a
+/// source and each later value one step further from it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FluidDefinition {
+    /// Fluid ticks between two flow steps.
+    pub ticks_per_step: u32,
+    /// Factor applied to player movement while inside the fluid.
+    pub movement_multiplier: f32,
+}
+
+/// A named state property, packed into `Voxel::state` in declaration order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockProperty {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl BlockProperty {
+    fn bits(&self) -> u32 {
+        if self.values.len() <= 1 {
+            0
+        } else {
+            usize::BITS - (self.values.len() - 1).leading_zeros()
+        }
+    }
+}
+
+impl BlockDefinition {
+    pub fn color(&self) -> Color {
+        let [red, green, blue, alpha] = self.color;
+        Color::srgba(red, green, blue, alpha)
+    }
+}
+
+#[derive(Deserialize)]
+struct BlockDefinitionFile {
+    blocks: Vec<BlockDefinition>,
+}
+
+/// Block definitions indexed by their numeric id.
+///
+/// The registry is cheap to clone so that chunk generation, meshing and collider tasks can carry
+/// their own handle into the async task pool.
+#[derive(Resource, Clone)]
+pub struct BlockRegistry {
+    definitions: Arc<Vec<Option<BlockDefinition>>>,
+    names: Arc<HashMap<String, BlockId>>,
+    hotbar: Arc<Vec<BlockId>>,
+}
+
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        match Self::load(Path::new(BLOCK_DEFINITIONS_PATH)) {
+            Ok(registry) => registry,
+            Err(error) => {
+                warn!("Failed to load block definitions, using built-in defaults: {error}");
+                Self::from_json(DEFAULT_BLOCK_DEFINITIONS)
+                    .expect("built-in block definitions are valid")
+            }
+        }
+    }
+}
+
+impl BlockRegistry {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        Self::from_json(&source)
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, String> {
+        let file: BlockDefinitionFile =
+            serde_json::from_str(source).map_err(|error| error.to_string())?;
+        Self::from_definitions(file.blocks)
+    }
+
+    pub fn from_definitions(blocks: Vec<BlockDefinition>) -> Result<Self, String> {
+        let mut definitions: Vec<Option<BlockDefinition>> = Vec::new();
+        let mut names = HashMap::default();
+        let mut hotbar = Vec::new();
+
+        for block in blocks {
+            let id = BlockId(block.id);
+            let index = block.id as usize;
+            if definitions.len() <= index {
+                definitions.resize(index + 1, None);
+            }
+            if definitions[index].is_some() {
+                return Err(format!("Duplicate block id: {}", block.id));
+            }
+            if names.insert(block.name.clone(), id).is_some() {
+                return Err(format!("Duplicate block name: {}", block.name));
+            }
+            validate_properties(&block)?;
+            if let Some(slot) = block.hotbar_slot {
+                hotbar.push((slot, id));
+            }
+            definitions[index] = Some(block);
+        }
+
+        match definitions.first() {
+            Some(Some(air)) if !air.solid && !air.opaque => {}
+            _ => {
+                return Err(
+                    "Block id 0 must be defined as a non-solid, non-opaque air block".into(),
+                );
+            }
+        }
+
+        let biome_blocks = Biome::ALL.into_iter().flat_map(|biome| {
+            let terrain = biome.terrain();
+            [terrain.surface, terrain.subsurface]
+        });
+        if let Some(missing) = GENERATED_BLOCK_NAMES
+            .into_iter()
+            .chain(biome_blocks)
+            .chain(OreSettings::BLOCK_NAMES)
+            .find(|name| !names.contains_key(*name))
+        {
+            return Err(format!(
+                "Block {missing} is used by world generation but not defined"
+            ));
+        }
+
+        hotbar.sort_by_key(|(slot, _)| *slot);
+
+        Ok(Self {
+            definitions: Arc::new(definitions),
+            names: Arc::new(names),
+            hotbar: Arc::new(hotbar.into_iter().map(|(_, id)| id).collect()),
+        })
+    }
+
+    pub fn get(&self, id: BlockId) -> Option<&BlockDefinition> {
+        self.definitions.get(id.0 as usize).and_then(Option::as_ref)
+    }
+
+    pub fn id_by_name(&self, name: &str) -> Option<BlockId> {
+        self.names.get(name).copied()
+    }
+
+    /// Id of a block the world generators place. Loading fails when one of them is missing, so
+    /// only a name left out of `GENERATED_BLOCK_NAMES` can panic here.
+    pub fn generated_block(&self, name: &str) -> BlockId {
+        self.id_by_name(name)
+            .unwrap_or_else(|| panic!("Generated block {name} is not defined"))
+    }
+
+    pub fn is_solid(&self, id: BlockId) -> bool {
+        self.get(id).is_some_and(|block| block.solid)
+    }
+
+    pub fn is_opaque(&self, id: BlockId) -> bool {
+        self.get(id).is_some_and(|block| block.opaque)
+    }
+
+    pub fn color(&self, id: BlockId) -> Color {
+        self.get(id).map_or(Color::NONE, BlockDefinition::color)
+    }
+
+    pub fn display_name(&self, id: BlockId) -> &str {
+        self.get(id)
+            .map_or("Unknown", |block| block.display_name.as_str())
+    }
+
+    pub fn falls(&self, id: BlockId) -> bool {
+        self.get(id).is_some_and(|block| block.falls)
+    }
+
+    pub fn light_emission(&self, id: BlockId) -> u8 {
+        self.get(id).map_or(0, |block| block.light_emission)
+    }
+
+    pub fn fluid(&self, id: BlockId) -> Option<&FluidDefinition> {
+        self.get(id).and_then(|block| block.fluid.as_ref())
+    }
+
+    pub fn property(&self, id: BlockId, property: &str) -> Option<&BlockProperty> {
+        self.property_layout(id, property)
+            .map(|(_, _, declared)| declared)
+    }
+
+    /// Returns the value of a state property, or `None` if the block does not declare it.
+    pub fn state_value(&self, voxel: Voxel, property: &str) -> Option<&str> {
+        let index = self.state_index(voxel, property)?;
+        self.property(voxel.block, property)?
+            .values
+            .get(index)
+            .map(String::as_str)
+    }
+
+    /// Returns the position of a state property's value in its declared `values`.
+    pub fn state_index(&self, voxel: Voxel, property: &str) -> Option<usize> {
+        let (shift, bits, declared) = self.property_layout(voxel.block, property)?;
+        let index = ((voxel.state >> shift) & state_mask(bits)) as usize;
+        (index < declared.values.len()).then_some(index)
+    }
+
+    /// Sets a state property, leaving the voxel unchanged if the block does not declare the
+    /// property or the value.
+    pub fn with_state_value(&self, voxel: Voxel, property: &str, value: &str) -> Voxel {
+        let Some(index) = self
+            .property(voxel.block, property)
+            .and_then(|declared| declared.values.iter().position(|entry| entry == value))
+        else {
+            return voxel;
+        };
+        self.with_state_index(voxel, property, index)
+    }
+
+    /// Sets a state property by value position, leaving the voxel unchanged if the block does
+    /// not declare the property or the index is out of range.
+    pub fn with_state_index(&self, voxel: Voxel, property: &str, index: usize) -> Voxel {
+        let Some((shift, bits, declared)) = self.property_layout(voxel.block, property) else {
+            return voxel;
+        };
+        if index >= declared.values.len() {
+            return voxel;
+        }
+
+        let mask = state_mask(bits) << shift;
+        Voxel::with_state(
+            voxel.block,
+            (voxel.state & !mask) | ((index as u16) << shift),
+        )
+    }
+
+    /// Formats every declared property as `name=value`, for debug displays.
+    pub fn describe_state(&self, voxel: Voxel) -> String {
+        self.get(voxel.block)
+            .map(|block| {
+                block
+                    .properties
+                    .iter()
+                    .filter_map(|property| {
+                        self.state_value(voxel, &property.name)
+                            .map(|value| format!("{}={value}", property.name))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    }
+
+    fn property_layout(&self, id: BlockId, property: &str) -> Option<(u32, u32, &BlockProperty)> {
+        let mut shift = 0;
+        for declared in &self.get(id)?.properties {
+            let bits = declared.bits();
+            if declared.name == property {
+                return Some((shift, bits, declared));
+            }
+            shift += bits;
+        }
+        None
+    }
+
+    /// Blocks shown in the hotbar, ordered by their declared slot.
+    pub fn hotbar_blocks(&self) -> &[BlockId] {
+        &self.hotbar
+    }
+}
+
+fn validate_properties(block: &BlockDefinition) -> Result<(), String> {
+    let mut total_bits = 0;
+    for (index, property) in block.properties.iter().enumerate() {
+        if property.values.is_empty() {
+            return Err(format!(
+                "Property {} of block {} has no values",
+                property.name, block.name
+            ));
+        }
+        if block.properties[..index]
+            .iter()
+            .any(|other| other.name == property.name)
+        {
+            return Err(format!(
+                "Duplicate property {} on block {}",
+                property.name, block.name
+            ));
+        }
+        total_bits += property.bits();
+    }
+
+    if block.falls && !block.solid {
+        return Err(format!("Falling block {} must be solid", block.name));
+    }
+
+    if block.light_emission > MAX_LIGHT_LEVEL {
+        return Err(format!(
+            "Block {} emits light level {}, at most {MAX_LIGHT_LEVEL} is allowed",
+            block.name, block.light_emission
+        ));
+    }
+
+    if block.fluid.is_some() {
+        if block.solid {
+            return Err(format!("Fluid block {} must not be solid", block.name));
+        }
+        if !block
+            .properties
+            .iter()
+            .any(|property| property.name == "level")
+        {
+            return Err(format!(
+                "Fluid block {} must declare a level property",
+                block.name
+            ));
+        }
+    }
+
+    if total_bits > u16::BITS {
+        return Err(format!(
+            "Properties of block {} need {total_bits} state bits, at most {} are available",
+            block.name,
+            u16::BITS
+        ));
+    }
+    Ok(())
+}
+
+fn state_mask(bits: u32) -> u16 {
+    ((1u32 << bits) - 1) as u16
+}
+
+pub struct BlockPlugin;
+
+impl Plugin for BlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlockRegistry>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_definitions_resolve_builtin_blocks() {
+        let registry = BlockRegistry::from_json(DEFAULT_BLOCK_DEFINITIONS).unwrap();
+
+        assert!(!registry.is_solid(BlockId::AIR));
+        let stone = registry.id_by_name("stone").unwrap();
+        assert!(registry.is_solid(stone));
+        assert!(registry.is_opaque(stone));
+        assert_eq!(registry.display_name(stone), "Stone");
+        assert_eq!(registry.hotbar_blocks().len(), 10);
+        let glass = registry.id_by_name("glass").unwrap();
+        assert!(registry.is_solid(glass));
+        assert!(!registry.is_opaque(glass));
+        let lamp = registry.id_by_name("lamp").unwrap();
+        assert_eq!(registry.light_emission(lamp), MAX_LIGHT_LEVEL);
+        assert_eq!(registry.light_emission(stone), 0);
+    }
+
+    #[test]
+    fn state_properties_round_trip() {
+        let registry = BlockRegistry::from_json(DEFAULT_BLOCK_DEFINITIONS).unwrap();
+        let log = Voxel::new(registry.id_by_name("log").unwrap());
+
+        assert_eq!(registry.state_value(log, "facing"), Some("positive_y"));
+        let rotated = registry.with_state_value(log, "facing", "negative_z");
+        assert_eq!(registry.state_value(rotated, "facing"), Some("negative_z"));
+        assert_eq!(registry.describe_state(rotated), "facing=negative_z");
+
+        assert_eq!(registry.with_state_value(log, "facing", "sideways"), log);
+        let stone = Voxel::new(registry.id_by_name("stone").unwrap());
+        assert_eq!(
+            registry.with_state_value(stone, "facing", "negative_z"),
+            stone
+        );
+        assert_eq!(registry.state_value(stone, "facing"), None);
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_and_missing_air() {
+        let block = |id: u16, name: &str, solid: bool| BlockDefinition {
+            id,
+            name: name.to_string(),
+            display_name: name.to_string(),
+            color: [1.0; 4],
+            solid,
+            opaque: solid,
+            hardness: 1.0,
+            hotbar_slot: None,
+            properties: Vec::new(),
+            fluid: None,
+            falls: false,
+            light_emission: 0,
+        };
+
+        assert!(
+            BlockRegistry::from_definitions(vec![block(0, "air", false), block(0, "stone", true)])
+                .is_err()
+        );
+        assert!(BlockRegistry::from_definitions(vec![block(1, "stone", true)]).is_err());
+    }
+
+    #[test]
+    fn rejects_definitions_missing_generated_blocks() {
+        let mut file: BlockDefinitionFile =
+            serde_json::from_str(DEFAULT_BLOCK_DEFINITIONS).unwrap();
+        file.blocks.retain(|block| block.name != "snow");
+
+        let error = BlockRegistry::from_definitions(file.blocks).err().unwrap();
+        assert!(error.contains("snow"), "{error}");
+    }
+}
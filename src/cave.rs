@@ -0,0 +1,322 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use noise::{NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+use crate::rng::{SeededRng, cell_key};
+use crate::terrain::TerrainGenerator;
+use crate::voxel::VOXEL_SIZE;
+use crate::world::{CHUNK_VOXELS_SIZE, ChunkCoord};
+
+/// Side of the cubic regions that each start at most one worm cave, in voxels.
+const WORM_REGION_VOXELS: i32 = 128;
+/// Distance a worm moves between carved spheres, in voxels.
+const WORM_STEP_VOXELS: f32 = 6.0;
+const WORM_MIN_STEPS: i32 = 20;
+const WORM_MAX_STEPS: i32 = 56;
+const WORM_MIN_RADIUS_VOXELS: i32 = 10;
+const WORM_MAX_RADIUS_VOXELS: i32 = 20;
+/// Farthest a worm carves from its starting cell, in voxels.
+const WORM_REACH_VOXELS: i32 = WORM_MAX_STEPS * WORM_STEP_VOXELS as i32 + WORM_MAX_RADIUS_VOXELS;
+/// Mixed into region keys so entrances roll independently of the deep worms.
+const ENTRANCE_KEY: u64 = 0x656e_7472_616e_6365;
+
+/// Tunables of the noodle and worm caves.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaveSettings {
+    /// Frequency of the two noise fields whose shared zero crossings form noodle caves, per
+    /// meter.
+    pub noodle_frequency: f64,
+    /// How close to zero both fields must be for a cell to lie inside a noodle; wider values
+    /// give thicker tunnels.
+    pub noodle_width: f64,
+    /// Chance of a region below the terrain band starting a worm cave.
+    pub worm_chance: f32,
+    /// Chance of a column of regions opening a worm cave at the surface.
+    pub entrance_chance: f32,
+    /// Solid voxels that must lie above a cell before caves other than entrances may carve it.
+    pub roof_voxels: i32,
+}
+
+impl Default for CaveSettings {
+    fn default() -> Self {
+        Self {
+            noodle_frequency: 0.12,
+            noodle_width: 0.1,
+            worm_chance: 0.5,
+            entrance_chance: 0.2,
+            roof_voxels: 6,
+        }
+    }
+}
+
+/// How a worm cave carves the cells it passes through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tunnel {
+    /// Carves only rock with enough roof above it, like noodle caves.
+    Underground,
+    /// Carves through the surface too, opening the cave network to the sky.
+    Entrance,
+}
+
+/// A worm cave as the spheres it carves, centered in global cell space with radii in voxels.
+struct Worm {
+    tunnel: Tunnel,
+    spheres: Vec<(Vec3, f32)>,
+}
+
+/// Cells of one chunk that worm caves pass through.
+pub struct WormMask {
+    /// Indexed by local `x`, `y`, `z`; empty when no worm reaches the chunk.
+    tunnels: Vec<Option<Tunnel>>,
+}
+
+impl WormMask {
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<Tunnel> {
+        if self.tunnels.is_empty() {
+            return None;
+        }
+        self.tunnels[Self::index(x, y, z)]
+    }
+
+    fn index(x: usize, y: usize, z: usize) -> usize {
+        x + CHUNK_VOXELS_SIZE * (y + CHUNK_VOXELS_SIZE * z)
+    }
+}
+
+/// Connected cave networks: noodle caves along ridged noise, and worm caves carved by seeded
+/// random walks. Every worm is a pure function of the seed and the region it starts in, so
+/// the part of it in each chunk comes out the same whichever chunk is generated first.
+pub struct Caves {
+    seed: u32,
+    settings: CaveSettings,
+    noodle_a: Perlin,
+    noodle_b: Perlin,
+}
+
+impl Caves {
+    pub fn new(seed: u32, settings: CaveSettings) -> Self {
+        Self {
+            seed,
+            settings,
+            noodle_a: Perlin::new(seed.wrapping_add(424242)),
+            noodle_b: Perlin::new(seed.wrapping_add(242424)),
+        }
+    }
+
+    /// Whether a cell lies inside a noodle cave. Each field is folded into a ridge along its
+    /// zero crossings, and a noodle runs where both ridges meet.
+    pub fn is_noodle(&self, cell: IVec3) -> bool {
+        let width = self.settings.noodle_width;
+        let point =
+            (cell.as_dvec3() * VOXEL_SIZE as f64 * self.settings.noodle_frequency).to_array();
+        let a = self.noodle_a.get(point);
+        if a.abs() > width {
+            return false;
+        }
+        let b = self.noodle_b.get(point);
+        a * a + b * b < width * width
+    }
+
+    /// Marks the cells of the chunk that any nearby worm cave passes through.
+    pub fn worm_mask(&self, terrain: &TerrainGenerator, coord: ChunkCoord) -> WormMask {
+        let chunk_min = coord.origin_cell();
+        let chunk_max = chunk_min + IVec3::splat(CHUNK_VOXELS_SIZE as i32 - 1);
+        let mut mask = WormMask {
+            tunnels: Vec::new(),
+        };
+
+        for worm in self.worms_near(terrain, chunk_min, chunk_max) {
+            for (center, radius) in worm.spheres {
+                let min = (center - radius).floor().as_ivec3().max(chunk_min);
+                let max = (center + radius).ceil().as_ivec3().min(chunk_max);
+                if min.cmpgt(max).any() {
+                    continue;
+                }
+
+                if mask.tunnels.is_empty() {
+                    mask.tunnels = vec![None; CHUNK_VOXELS_SIZE.pow(3)];
+                }
+                for x in min.x..=max.x {
+                    for y in min.y..=max.y {
+                        for z in min.z..=max.z {
+                            let cell = IVec3::new(x, y, z);
+                            if cell.as_vec3().distance_squared(center) > radius * radius {
+                                continue;
+                            }
+                            let local = (cell - chunk_min).as_uvec3();
+                            let tunnel = &mut mask.tunnels[WormMask::index(
+                                local.x as usize,
+                                local.y as usize,
+                                local.z as usize,
+                            )];
+                            if *tunnel != Some(Tunnel::Entrance) {
+                                *tunnel = Some(worm.tunnel);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        mask
+    }
+
+    /// Worms starting close enough to reach the box between `min` and `max`.
+    fn worms_near(&self, terrain: &TerrainGenerator, min: IVec3, max: IVec3) -> Vec<Worm> {
+        let region_min = (min - WORM_REACH_VOXELS).div_euclid(IVec3::splat(WORM_REGION_VOXELS));
+        let region_max = (max + WORM_REACH_VOXELS).div_euclid(IVec3::splat(WORM_REGION_VOXELS));
+        let settings = terrain.settings();
+        let lowest_surface_y = (settings.min_height_meters / VOXEL_SIZE).floor() as i32;
+        let highest_surface_y = (settings.max_height_meters / VOXEL_SIZE).floor() as i32;
+        let reaches_surface = max.y >= lowest_surface_y - WORM_REACH_VOXELS
+            && min.y <= highest_surface_y + WORM_MAX_RADIUS_VOXELS;
+
+        let mut worms = Vec::new();
+        for region_x in region_min.x..=region_max.x {
+            for region_z in region_min.z..=region_max.z {
+                if reaches_surface
+                    && let Some(worm) = self.entrance_worm(terrain, region_x, region_z)
+                {
+                    worms.push(worm);
+                }
+                for region_y in region_min.y..=region_max.y {
+                    if let Some(worm) =
+                        self.region_worm(IVec3::new(region_x, region_y, region_z), lowest_surface_y)
+                    {
+                        worms.push(worm);
+                    }
+                }
+            }
+        }
+        worms
+    }
+
+    /// The worm starting in a region, if it rolls one and starts below the terrain band.
+    fn region_worm(&self, region: IVec3, lowest_surface_y: i32) -> Option<Worm> {
+        let mut rng = SeededRng::new(self.seed, cell_key(region));
+        if rng.unit() >= self.settings.worm_chance {
+            return None;
+        }
+        let start = region * WORM_REGION_VOXELS
+            + IVec3::new(
+                rng.range(0, WORM_REGION_VOXELS),
+                rng.range(0, WORM_REGION_VOXELS),
+                rng.range(0, WORM_REGION_VOXELS),
+            );
+        if start.y >= lowest_surface_y {
+            return None;
+        }
+
+        let pitch = (rng.unit() - 0.5) * 0.6;
+        Some(Worm {
+            tunnel: Tunnel::Underground,
+            spheres: walk(&mut rng, start.as_vec3(), pitch),
+        })
+    }
+
+    /// The worm that opens at the surface of a column of regions, if it rolls one and the
+    /// ground there is dry.
+    fn entrance_worm(
+        &self,
+        terrain: &TerrainGenerator,
+        region_x: i32,
+        region_z: i32,
+    ) -> Option<Worm> {
+        let key = cell_key(IVec3::new(region_x, 0, region_z)) ^ ENTRANCE_KEY;
+        let mut rng = SeededRng::new(self.seed, key);
+        if rng.unit() >= self.settings.entrance_chance {
+            return None;
+        }
+        let x = region_x * WORM_REGION_VOXELS + rng.range(0, WORM_REGION_VOXELS);
+        let z = region_z * WORM_REGION_VOXELS + rng.range(0, WORM_REGION_VOXELS);
+        let (surface_y, _) = terrain.surface_column(x, z);
+        if surface_y <= terrain.sea_level_cell() {
+            return None;
+        }
+
+        // Head steeply down at first, flattening out as the pitch relaxes.
+        Some(Worm {
+            tunnel: Tunnel::Entrance,
+            spheres: walk(&mut rng, IVec3::new(x, surface_y, z).as_vec3(), -0.9),
+        })
+    }
+}
+
+/// Random walk from `start`, turning a little every step.
+fn walk(rng: &mut SeededRng, start: Vec3, mut pitch: f32) -> Vec<(Vec3, f32)> {
+    let steps = rng.range(WORM_MIN_STEPS, WORM_MAX_STEPS + 1);
+    let radius = rng.range(WORM_MIN_RADIUS_VOXELS, WORM_MAX_RADIUS_VOXELS + 1) as f32;
+    let mut yaw = rng.unit() * TAU;
+    let mut position = start;
+
+    let mut spheres = Vec::with_capacity(steps as usize);
+    for step in 0..steps {
+        // Swell and narrow along the way, never past the maximum radius.
+        spheres.push((position, radius * (0.75 + 0.25 * (step as f32 * 0.3).sin())));
+
+        let direction = Vec3::new(
+            yaw.cos() * pitch.cos(),
+            pitch.sin(),
+            yaw.sin() * pitch.cos(),
+        );
+        position += direction * WORM_STEP_VOXELS;
+        yaw += (rng.unit() - 0.5) * 0.6;
+        pitch = (pitch * 0.9 + (rng.unit() - 0.5) * 0.4).clamp(-0.9, 0.9);
+    }
+    spheres
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockRegistry;
+    use crate::terrain::TerrainSettings;
+    use crate::world::{Chunk, split_cell};
+
+    fn is_open(terrain: &TerrainGenerator, cell: IVec3) -> bool {
+        let (coord, x, y, z) = split_cell(cell);
+        let mut chunk = Chunk::new(coord);
+        terrain.generate_chunk(&mut chunk);
+        chunk.get_voxel(x, y, z).unwrap().block.is_air()
+    }
+
+    #[test]
+    fn worm_caves_cross_chunk_borders_and_open_at_the_surface() {
+        let registry = BlockRegistry::default();
+        let terrain = TerrainGenerator::new(9, &registry, TerrainSettings::default());
+        let caves = Caves::new(9, CaveSettings::default());
+        let worms = caves.worms_near(
+            &terrain,
+            IVec3::new(-600, -600, -600),
+            IVec3::new(600, 0, 600),
+        );
+
+        // A deep worm is carved in every chunk it passes, each generated on its own.
+        let deep = worms
+            .iter()
+            .find(|worm| {
+                let first = ChunkCoord::from_cell(worm.spheres[0].0.as_ivec3());
+                worm.tunnel == Tunnel::Underground
+                    && worm.spheres.iter().all(|(center, _)| center.y < -64.0)
+                    && worm
+                        .spheres
+                        .iter()
+                        .any(|(center, _)| ChunkCoord::from_cell(center.as_ivec3()) != first)
+            })
+            .expect("a deep worm crossing chunk borders");
+        for (center, _) in deep.spheres.iter().step_by(8) {
+            assert!(is_open(&terrain, center.as_ivec3()));
+        }
+
+        // Entrances cut through the ground the terrain puts at their start.
+        let entrance = worms
+            .iter()
+            .find(|worm| worm.tunnel == Tunnel::Entrance)
+            .expect("a cave entrance");
+        let start = entrance.spheres[0].0.as_ivec3();
+        assert_eq!(terrain.surface_column(start.x, start.z).0, start.y);
+        assert!(is_open(&terrain, start));
+    }
+}
@@ -6,8 +6,10 @@ use serde_json::json;
 use crate::AppState;
 use crate::physics::{ChunkPhysics, PendingPhysicsCollider};
 use crate::player::{NeedsPhysicsRefresh, NeedsRenderRefresh, Player, PlayerCamera};
-use crate::render::{ChunkMesh, PendingRenderMesh};
-use crate::world::{Chunk, InitialWorldGeneration, World as GameWorld, chunk_world_size};
+use crate::render::{ChunkMesh, ChunkTransparentMesh, PendingRenderMesh};
+use crate::world::{
+    Chunk, InitialWorldGeneration, ViewDistanceSettings, World as GameWorld, chunk_world_size,
+};
 
 const BRP_HOST: [u8; 4] = [127, 0, 0, 1];
 const BRP_PORT: u16 = 15702;
@@ -28,11 +30,16 @@ impl Plugin for DebugRemotePlugin {
     }
 }
 
+#[allow(
+    clippy::too_many_arguments,
+    reason = "one query per reported entity count"
+)]
 fn debug_summary(
     _: In<Option<serde_json::Value>>,
     app_state: Res<State<AppState>>,
     game_world: Res<GameWorld>,
     generation: Res<InitialWorldGeneration>,
+    view: Res<ViewDistanceSettings>,
     players: Query<(), With<Player>>,
     cameras: Query<(), With<PlayerCamera>>,
     chunks: Query<(), With<Chunk>>,
@@ -52,7 +59,10 @@ fn debug_summary(
         "world": {
             "registered_chunks": game_world.chunks.len(),
             "pending_generation_chunks": game_world.pending_chunks.len(),
+            "queued_generation_chunks": game_world.queued_chunks.len(),
             "chunk_world_size": chunk_world_size(),
+            "render_distance_chunks": view.render_distance_chunks,
+            "simulation_distance_chunks": view.simulation_distance_chunks,
         },
         "initial_generation": {
             "started": generation.started,
@@ -104,11 +114,16 @@ fn debug_player(
     }))
 }
 
+#[allow(
+    clippy::too_many_arguments,
+    reason = "one query per reported entity count"
+)]
 fn debug_chunks(
     _: In<Option<serde_json::Value>>,
     game_world: Res<GameWorld>,
     chunks: Query<&Chunk>,
     chunk_meshes: Query<(), With<ChunkMesh>>,
+    transparent_meshes: Query<(), With<ChunkTransparentMesh>>,
     chunk_physics: Query<(), With<ChunkPhysics>>,
     pending_render: Query<(), With<PendingRenderMesh>>,
     pending_physics: Query<(), With<PendingPhysicsCollider>>,
@@ -120,9 +135,13 @@ fn debug_chunks(
         .take(16)
         .map(|chunk| {
             json!({
-                "coord": { "x": chunk.coord.x, "z": chunk.coord.z },
+                "coord": { "x": chunk.coord.x, "y": chunk.coord.y, "z": chunk.coord.z },
                 "revision": chunk.revision,
+                "light_revision": chunk.light_revision,
                 "modified": chunk.modified,
+                "empty": chunk.is_empty(),
+                "voxel_bytes": chunk.heap_size(),
+                "content_hash": format!("{:016x}", chunk.content_hash()),
             })
         })
         .collect::<Vec<_>>();
@@ -131,15 +150,21 @@ fn debug_chunks(
         let coord = &value["coord"];
         (
             coord["x"].as_i64().unwrap_or_default(),
+            coord["y"].as_i64().unwrap_or_default(),
             coord["z"].as_i64().unwrap_or_default(),
         )
     });
 
+    let voxel_bytes = chunks.iter().map(|chunk| chunk.heap_size()).sum::<usize>();
+
     Ok(json!({
         "registered_chunks": game_world.chunks.len(),
+        "voxel_bytes": voxel_bytes,
         "pending_generation_chunks": game_world.pending_chunks.len(),
         "chunk_components": chunks.iter().count(),
+        "empty_chunks": chunks.iter().filter(|chunk| chunk.is_empty()).count(),
         "chunk_meshes": chunk_meshes.iter().count(),
+        "chunk_transparent_meshes": transparent_meshes.iter().count(),
         "chunk_physics": chunk_physics.iter().count(),
         "pending_render_meshes": pending_render.iter().count(),
         "pending_physics_colliders": pending_physics.iter().count(),
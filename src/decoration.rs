@@ -0,0 +1,465 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::biome::Biome;
+use crate::block::BlockRegistry;
+use crate::rng::{SeededRng, cell_key, mix};
+use crate::terrain::TerrainGenerator;
+use crate::voxel::{BlockId, VOXEL_SIZE, Voxel};
+use crate::world::{CHUNK_VOXELS_SIZE, Chunk};
+
+/// Where trees, boulders and ruins grow and how big they get. Sizes are in voxels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecorationSettings {
+    /// Side of the square grid cells that each root at most one feature, in voxels.
+    pub region_voxels: i32,
+    /// Distance feature roots keep from the sides of their region, in voxels.
+    pub root_margin_voxels: i32,
+    pub plains: FeatureChances,
+    pub desert: FeatureChances,
+    pub mountains: FeatureChances,
+    pub tundra: FeatureChances,
+    pub tree_canopy_radius: SizeRange,
+    pub tree_trunk_height: SizeRange,
+    /// Horizontal and vertical radius of boulders.
+    pub boulder_radius: SizeRange,
+    pub boulder_height: SizeRange,
+    pub ruin_half_width: SizeRange,
+    /// Tallest a ruin's walls stand, at least 2.
+    pub ruin_wall_height: SizeRange,
+}
+
+impl Default for DecorationSettings {
+    fn default() -> Self {
+        let chances = |tree, boulder, ruin| FeatureChances {
+            tree,
+            boulder,
+            ruin,
+        };
+        let size = |min, max| SizeRange { min, max };
+        Self {
+            region_voxels: 40,
+            root_margin_voxels: 6,
+            plains: chances(0.45, 0.05, 0.02),
+            desert: chances(0.0, 0.06, 0.05),
+            mountains: chances(0.08, 0.25, 0.0),
+            tundra: chances(0.2, 0.05, 0.0),
+            tree_canopy_radius: size(7, 11),
+            tree_trunk_height: size(22, 36),
+            boulder_radius: size(4, 8),
+            boulder_height: size(3, 6),
+            ruin_half_width: size(7, 11),
+            ruin_wall_height: size(6, 14),
+        }
+    }
+}
+
+impl DecorationSettings {
+    /// Highest any feature reaches above the terrain surface, in meters.
+    pub fn max_height_meters(&self) -> f32 {
+        let settings = self.sanitized();
+        let tree = 1 + settings.tree_trunk_height.max + settings.tree_canopy_radius.max;
+        let boulder = settings.boulder_height.max / 3 + settings.boulder_height.max;
+        let ruin = 1 + settings.ruin_wall_height.max;
+        tree.max(boulder).max(ruin) as f32 * VOXEL_SIZE
+    }
+
+    /// Farthest any feature reaches sideways from its root, in voxels.
+    fn max_radius_voxels(&self) -> i32 {
+        self.tree_canopy_radius
+            .max
+            .max(self.boulder_radius.max)
+            .max(self.ruin_half_width.max)
+    }
+
+    fn chances(&self, biome: Biome) -> FeatureChances {
+        match biome {
+            Biome::Plains => self.plains,
+            Biome::Desert => self.desert,
+            Biome::Mountains => self.mountains,
+            Biome::Tundra => self.tundra,
+        }
+    }
+
+    /// The same settings with every range non-empty and every size big enough to draw, so a
+    /// hand-edited file cannot break generation.
+    fn sanitized(mut self) -> Self {
+        self.region_voxels = self.region_voxels.max(1);
+        self.root_margin_voxels = self
+            .root_margin_voxels
+            .clamp(0, (self.region_voxels - 1) / 2);
+        for (range, smallest) in [
+            (&mut self.tree_canopy_radius, 1),
+            (&mut self.tree_trunk_height, 1),
+            (&mut self.boulder_radius, 1),
+            (&mut self.boulder_height, 1),
+            (&mut self.ruin_half_width, 1),
+            (&mut self.ruin_wall_height, 2),
+        ] {
+            range.min = range.min.max(smallest);
+            range.max = range.max.max(range.min);
+        }
+        self
+    }
+}
+
+/// Chance of each feature kind being rooted in a region of one biome.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeatureChances {
+    pub tree: f32,
+    pub boulder: f32,
+    pub ruin: f32,
+}
+
+impl FeatureChances {
+    fn chance(&self, kind: FeatureKind) -> f32 {
+        match kind {
+            FeatureKind::Tree => self.tree,
+            FeatureKind::Boulder => self.boulder,
+            FeatureKind::Ruin => self.ruin,
+        }
+    }
+}
+
+/// Smallest and largest value of a feature dimension, both inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SizeRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl SizeRange {
+    fn roll(self, rng: &mut SeededRng) -> i32 {
+        rng.range(self.min, self.max + 1)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FeatureKind {
+    Tree,
+    Boulder,
+    Ruin,
+}
+
+/// How a feature voxel combines with the terrain already in its cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placement {
+    Replace,
+    IntoAir,
+}
+
+/// A feature rooted at a global cell. Its voxels are a pure function of the root, size and
+/// variant, so every chunk it overlaps can draw its own part without the others being loaded.
+#[derive(Clone, Copy, Debug)]
+struct Feature {
+    kind: FeatureKind,
+    root: IVec3,
+    /// Kind-specific dimensions: canopy radius and trunk height for trees, radii for boulders,
+    /// half width and wall height for ruins.
+    size: IVec3,
+    variant: u64,
+}
+
+/// Block ids the decorations place, resolved by name from the registry.
+struct DecorationBlocks {
+    log: BlockId,
+    leaves: BlockId,
+    stone: BlockId,
+    gravel: BlockId,
+}
+
+impl DecorationBlocks {
+    fn resolve(registry: &BlockRegistry) -> Self {
+        let id = |name: &str| registry.generated_block(name);
+        Self {
+            log: id("log"),
+            leaves: id("leaves"),
+            stone: id("stone"),
+            gravel: id("gravel"),
+        }
+    }
+}
+
+/// Places trees, boulders and ruins from the world seed on top of generated terrain.
+pub struct Decorations {
+    seed: u32,
+    blocks: DecorationBlocks,
+    settings: DecorationSettings,
+}
+
+impl Decorations {
+    pub fn new(seed: u32, registry: &BlockRegistry, settings: DecorationSettings) -> Self {
+        Self {
+            seed,
+            blocks: DecorationBlocks::resolve(registry),
+            settings: settings.sanitized(),
+        }
+    }
+
+    /// Draws the part of every nearby feature that falls inside the chunk. Features are visited
+    /// in a fixed order, so overlapping features resolve the same way in every chunk.
+    pub fn decorate_chunk(&self, terrain: &TerrainGenerator, chunk: &mut Chunk) {
+        let chunk_min = chunk.coord.origin_cell();
+        let chunk_max = chunk_min + IVec3::splat(CHUNK_VOXELS_SIZE as i32 - 1);
+
+        for feature in self.features_near(terrain, chunk_min, chunk_max) {
+            let (local_min, local_max) = feature.bounds();
+            let min = (feature.root + local_min).max(chunk_min);
+            let max = (feature.root + local_max).min(chunk_max);
+            if min.cmpgt(max).any() {
+                continue;
+            }
+
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    for z in min.z..=max.z {
+                        let cell = IVec3::new(x, y, z);
+                        let Some((block, placement)) =
+                            feature.block_at(cell - feature.root, &self.blocks)
+                        else {
+                            continue;
+                        };
+
+                        let local = (cell - chunk_min).as_uvec3();
+                        let (lx, ly, lz) = (local.x as usize, local.y as usize, local.z as usize);
+                        if placement == Placement::IntoAir
+                            && chunk
+                                .get_voxel(lx, ly, lz)
+                                .is_some_and(|voxel| !voxel.block.is_air())
+                        {
+                            continue;
+                        }
+                        chunk.set_voxel(lx, ly, lz, Voxel::new(block));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Features whose bounds may reach into the box between `min` and `max`, in region order.
+    fn features_near(&self, terrain: &TerrainGenerator, min: IVec3, max: IVec3) -> Vec<Feature> {
+        let radius = self.settings.max_radius_voxels();
+        let region_size = IVec3::splat(self.settings.region_voxels);
+        let region_min = (min - radius).div_euclid(region_size);
+        let region_max = (max + radius).div_euclid(region_size);
+
+        let mut features = Vec::new();
+        for region_x in region_min.x..=region_max.x {
+            for region_z in region_min.z..=region_max.z {
+                let Some(feature) = self.region_feature(terrain, region_x, region_z) else {
+                    continue;
+                };
+
+                let (local_min, local_max) = feature.bounds();
+                let feature_min = feature.root + local_min;
+                let feature_max = feature.root + local_max;
+                if feature_min.cmple(max).all() && feature_max.cmpge(min).all() {
+                    features.push(feature);
+                }
+            }
+        }
+        features
+    }
+
+    /// The feature rooted in a grid region, if its roll for the local biome comes up and its root
+    /// is above water.
+    fn region_feature(
+        &self,
+        terrain: &TerrainGenerator,
+        region_x: i32,
+        region_z: i32,
+    ) -> Option<Feature> {
+        let settings = &self.settings;
+        let mut rng = SeededRng::new(self.seed, cell_key(IVec3::new(region_x, 0, region_z)));
+        let (region, margin) = (settings.region_voxels, settings.root_margin_voxels);
+        let root_x = region_x * region + rng.range(margin, region - margin);
+        let root_z = region_z * region + rng.range(margin, region - margin);
+        let (surface_y, biome) = terrain.surface_column(root_x, root_z);
+        if surface_y < terrain.sea_level_cell() {
+            return None;
+        }
+
+        let roll = rng.unit();
+        let chances = settings.chances(biome);
+        let mut threshold = 0.0;
+        let kind = roll_order(biome).into_iter().find(|kind| {
+            threshold += chances.chance(*kind);
+            roll < threshold
+        })?;
+
+        let (root_y, size) = match kind {
+            FeatureKind::Tree => (
+                surface_y + 1,
+                IVec3::new(
+                    settings.tree_canopy_radius.roll(&mut rng),
+                    settings.tree_trunk_height.roll(&mut rng),
+                    0,
+                ),
+            ),
+            FeatureKind::Boulder => {
+                let size = IVec3::new(
+                    settings.boulder_radius.roll(&mut rng),
+                    settings.boulder_height.roll(&mut rng),
+                    settings.boulder_radius.roll(&mut rng),
+                );
+                (surface_y + size.y / 3, size)
+            }
+            FeatureKind::Ruin => (
+                surface_y + 1,
+                IVec3::new(
+                    settings.ruin_half_width.roll(&mut rng),
+                    settings.ruin_wall_height.roll(&mut rng),
+                    0,
+                ),
+            ),
+        };
+
+        Some(Feature {
+            kind,
+            root: IVec3::new(root_x, root_y, root_z),
+            size,
+            variant: rng.next(),
+        })
+    }
+}
+
+/// Order a region's roll is checked against the feature kinds of the biome. Mountains put
+/// boulders first, which keeps their features where earlier builds placed them.
+fn roll_order(biome: Biome) -> [FeatureKind; 3] {
+    match biome {
+        Biome::Mountains => [FeatureKind::Boulder, FeatureKind::Tree, FeatureKind::Ruin],
+        _ => [FeatureKind::Tree, FeatureKind::Boulder, FeatureKind::Ruin],
+    }
+}
+
+impl Feature {
+    /// Inclusive bounds of the feature relative to its root.
+    fn bounds(&self) -> (IVec3, IVec3) {
+        let size = self.size;
+        match self.kind {
+            FeatureKind::Tree => (
+                IVec3::new(-size.x, 0, -size.x),
+                IVec3::new(size.x, size.y + size.x, size.x),
+            ),
+            FeatureKind::Boulder => (-size, size),
+            FeatureKind::Ruin => (
+                IVec3::new(-size.x, -4, -size.x),
+                IVec3::new(size.x, size.y, size.x),
+            ),
+        }
+    }
+
+    /// Block the feature puts at `local`, relative to its root.
+    fn block_at(&self, local: IVec3, blocks: &DecorationBlocks) -> Option<(BlockId, Placement)> {
+        let size = self.size;
+        let jitter = mix(self.variant ^ cell_key(local));
+        match self.kind {
+            FeatureKind::Tree => {
+                let trunk_height = size.y;
+                if local.x.abs() <= 1 && local.z.abs() <= 1 && local.y < trunk_height {
+                    return Some((blocks.log, Placement::Replace));
+                }
+
+                // A slightly flattened ball of leaves around the top of the trunk, with a
+                // ragged rim.
+                let radius = size.x as f32;
+                let offset = (local - IVec3::Y * trunk_height).as_vec3() * Vec3::new(1.0, 1.3, 1.0);
+                let distance = offset.length();
+                let inside =
+                    distance <= radius && (distance < radius - 1.5 || !jitter.is_multiple_of(3));
+                inside.then_some((blocks.leaves, Placement::IntoAir))
+            }
+            FeatureKind::Boulder => {
+                let scaled = local.as_vec3() / size.as_vec3();
+                if scaled.length_squared() > 1.0 {
+                    return None;
+                }
+                let block = if jitter.is_multiple_of(5) {
+                    blocks.gravel
+                } else {
+                    blocks.stone
+                };
+                Some((block, Placement::Replace))
+            }
+            FeatureKind::Ruin => {
+                let half_width = size.x;
+                let ring = local.x.abs().max(local.z.abs());
+                if ring < half_width - 1 {
+                    return None;
+                }
+                // A doorway in the wall facing negative z.
+                if local.z < 0 && local.x.abs() <= 2 && local.y < 8 {
+                    return None;
+                }
+
+                // Each wall column crumbles to its own height.
+                let column = mix(self.variant ^ cell_key(IVec3::new(local.x, 0, local.z)));
+                let wall_height = 2 + (column % (size.y as u64 - 1)) as i32;
+                if local.y > wall_height {
+                    return None;
+                }
+                let block = if jitter.is_multiple_of(4) {
+                    blocks.gravel
+                } else {
+                    blocks.stone
+                };
+                Some((block, Placement::IntoAir))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::TerrainSettings;
+    use crate::world::{ChunkCoord, split_cell};
+
+    fn voxel_at(terrain: &TerrainGenerator, cell: IVec3) -> Voxel {
+        let (coord, x, y, z) = split_cell(cell);
+        let mut chunk = Chunk::new(coord);
+        terrain.generate_chunk(&mut chunk);
+        chunk.get_voxel(x, y, z).unwrap()
+    }
+
+    #[test]
+    fn trees_reach_into_neighbor_chunks_without_them_being_generated_together() {
+        let registry = BlockRegistry::default();
+        let terrain = TerrainGenerator::new(11, &registry, TerrainSettings::default());
+        let decorations = Decorations::new(11, &registry, DecorationSettings::default());
+
+        // A tree whose canopy crosses the x border of the chunk holding the top of its trunk.
+        let tree = decorations
+            .features_near(
+                &terrain,
+                IVec3::new(-400, -100, -400),
+                IVec3::new(400, 300, 400),
+            )
+            .into_iter()
+            .filter(|feature| feature.kind == FeatureKind::Tree)
+            .find(|tree| {
+                let top = tree.root + IVec3::Y * (tree.size.y - 1);
+                let canopy_edge = top + IVec3::new(tree.size.x - 2, 1, 0);
+                ChunkCoord::from_cell(top).x != ChunkCoord::from_cell(canopy_edge).x
+            })
+            .expect("a tree crossing a chunk border");
+
+        let top = tree.root + IVec3::Y * (tree.size.y - 1);
+        assert_eq!(voxel_at(&terrain, top).block, decorations.blocks.log);
+
+        let canopy_edge = top + IVec3::new(tree.size.x - 2, 1, 0);
+        assert_eq!(
+            voxel_at(&terrain, canopy_edge).block,
+            decorations.blocks.leaves
+        );
+
+        // Regenerating a chunk on its own gives the same voxels every time.
+        let (coord, ..) = split_cell(canopy_edge);
+        let mut first = Chunk::new(coord);
+        let mut second = Chunk::new(coord);
+        terrain.generate_chunk(&mut first);
+        terrain.generate_chunk(&mut second);
+        assert!(first.voxels().eq(second.voxels()));
+    }
+}
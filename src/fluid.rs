@@ -0,0 +1,319 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::block::{BlockRegistry, FluidDefinition};
+use crate::voxel::Voxel;
+use crate::world::{
+    CHUNK_VOXELS_SIZE, Chunk, FACE_NEIGHBORS, ViewDistanceSettings, VoxelAccess, VoxelChanged,
+    VoxelChanges, VoxelWorld,
+};
+
+pub const FLUID_TICK_SECONDS: f32 = 0.2;
+const MAX_FLUID_UPDATES_PER_TICK: usize = 2048;
+const FLUID_LEVEL_PROPERTY: &str = "level";
+
+const HORIZONTAL_NEIGHBORS: [IVec3; 4] = [IVec3::NEG_X, IVec3::X, IVec3::NEG_Z, IVec3::Z];
+
+/// Cells waiting for a fluid update, keyed by global voxel coordinate and due tick.
+#[derive(Resource)]
+pub struct FluidSimulation {
+    timer: Timer,
+    tick: u64,
+    scheduled: HashMap<IVec3, u64>,
+}
+
+impl Default for FluidSimulation {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(FLUID_TICK_SECONDS, TimerMode::Repeating),
+            tick: 0,
+            scheduled: HashMap::default(),
+        }
+    }
+}
+
+impl FluidSimulation {
+    /// Schedules a cell and its six neighbors `delay` ticks from now.
+    pub fn schedule_around(&mut self, cell: IVec3, delay: u64) {
+        self.schedule(cell, delay);
+        for offset in FACE_NEIGHBORS {
+            self.schedule(cell + offset, delay);
+        }
+    }
+
+    fn schedule(&mut self, cell: IVec3, delay: u64) {
+        let due = self.tick + delay.max(1);
+        self.scheduled
+            .entry(cell)
+            .and_modify(|scheduled| *scheduled = (*scheduled).min(due))
+            .or_insert(due);
+    }
+
+    /// Removes and returns the cells due this tick. Cells for which `paused` holds stay
+    /// scheduled and run as soon as they no longer are.
+    fn take_due(&mut self, paused: impl Fn(IVec3) -> bool) -> Vec<IVec3> {
+        let tick = self.tick;
+        let mut due = self
+            .scheduled
+            .iter()
+            .filter(|(cell, due)| **due <= tick && !paused(**cell))
+            .map(|(cell, _)| *cell)
+            .collect::<Vec<_>>();
+        // Lower cells first so a falling column settles before the cells above re-check it.
+        due.sort_by_key(|cell| (cell.y, cell.x, cell.z));
+        due.truncate(MAX_FLUID_UPDATES_PER_TICK);
+        for cell in &due {
+            self.scheduled.remove(cell);
+        }
+        due
+    }
+}
+
+pub struct FluidPlugin;
+
+impl Plugin for FluidPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FluidSimulation>()
+            .add_systems(OnEnter(AppState::LoadingWorld), reset_fluid_simulation)
+            .add_systems(
+                Update,
+                (
+                    schedule_loaded_fluids,
+                    schedule_changed_fluids,
+                    fluid_tick_system,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// Returns the fluid definition and level of a fluid voxel.
+pub fn fluid_state(blocks: &BlockRegistry, voxel: Voxel) -> Option<(&FluidDefinition, usize)> {
+    let fluid = blocks.fluid(voxel.block)?;
+    let level = blocks.state_index(voxel, FLUID_LEVEL_PROPERTY)?;
+    Some((fluid, level))
+}
+
+/// Returns the number of levels a fluid can spread across.
+pub fn fluid_level_count(blocks: &BlockRegistry, voxel: Voxel) -> usize {
+    blocks
+        .property(voxel.block, FLUID_LEVEL_PROPERTY)
+        .map_or(1, |property| property.values.len())
+}
+
+fn reset_fluid_simulation(mut simulation: ResMut<FluidSimulation>) {
+    *simulation = FluidSimulation::default();
+}
+
+/// Resumes flows that were still spreading when their chunk was saved or unloaded.
+fn schedule_loaded_fluids(
+    mut simulation: ResMut<FluidSimulation>,
+    chunk_query: Query<&Chunk, Added<Chunk>>,
+    blocks: Res<BlockRegistry>,
+) {
+    for chunk in chunk_query.iter() {
+        if !chunk.may_contain(|voxel| blocks.fluid(voxel.block).is_some()) {
+            continue;
+        }
+
+        let chunk_base = chunk.coord.origin_cell();
+        for x in 0..CHUNK_VOXELS_SIZE {
+            for y in 0..CHUNK_VOXELS_SIZE {
+                for z in 0..CHUNK_VOXELS_SIZE {
+                    if let Some(voxel) = chunk.get_voxel(x, y, z)
+                        && let Some((_, level)) = fluid_state(&blocks, voxel)
+                        && level > 0
+                    {
+                        let cell = chunk_base + IVec3::new(x as i32, y as i32, z as i32);
+                        simulation.schedule(cell, 1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Wakes fluids next to changed voxels, so a dug hole fills and a placed block cuts off a flow.
+fn schedule_changed_fluids(
+    mut simulation: ResMut<FluidSimulation>,
+    mut voxel_changes: MessageReader<VoxelChanged>,
+) {
+    for change in voxel_changes.read() {
+        simulation.schedule_around(change.cell, 1);
+    }
+}
+
+fn fluid_tick_system(
+    time: Res<Time>,
+    mut simulation: ResMut<FluidSimulation>,
+    mut voxels: VoxelWorld,
+    mut changes: VoxelChanges,
+    blocks: Res<BlockRegistry>,
+    view: Res<ViewDistanceSettings>,
+) {
+    simulation.timer.tick(time.delta());
+    if !simulation.timer.just_finished() {
+        return;
+    }
+
+    simulation.tick += 1;
+    let due = simulation.take_due(|cell| voxels.world().simulation_paused(cell, &view));
+    for cell in due {
+        // Slower fluids only step on multiples of their interval, however they were scheduled.
+        if let Some(fluid) = voxels.get(cell).and_then(|voxel| blocks.fluid(voxel.block)) {
+            let interval = u64::from(fluid.ticks_per_step.max(1));
+            let remainder = simulation.tick % interval;
+            if remainder != 0 {
+                simulation.schedule(cell, interval - remainder);
+                continue;
+            }
+        }
+
+        for (changed_cell, voxel) in step_fluid_cell(&voxels, &blocks, cell) {
+            if !voxels.set(changed_cell, voxel) {
+                continue;
+            }
+
+            changes.report(voxels.world(), changed_cell);
+        }
+    }
+}
+
+/// Computes the voxels a fluid cell changes this step: its own level, or the cells it spreads to.
+fn step_fluid_cell(
+    voxels: &VoxelWorld,
+    blocks: &BlockRegistry,
+    cell: IVec3,
+) -> Vec<(IVec3, Voxel)> {
+    let voxel_at = |cell: IVec3| voxels.get(cell);
+    let Some(voxel) = voxel_at(cell) else {
+        return Vec::new();
+    };
+    let Some((_, level)) = fluid_state(blocks, voxel) else {
+        return Vec::new();
+    };
+    let max_level = fluid_level_count(blocks, voxel) - 1;
+    if max_level == 0 {
+        return Vec::new();
+    }
+
+    let with_level = |level: usize| blocks.with_state_index(voxel, FLUID_LEVEL_PROPERTY, level);
+    let same_fluid_level = |cell: IVec3| {
+        voxel_at(cell)
+            .filter(|neighbor| neighbor.block == voxel.block)
+            .and_then(|neighbor| blocks.state_index(neighbor, FLUID_LEVEL_PROPERTY))
+    };
+
+    // Flowing cells are fed from above or by a shallower horizontal neighbor, and dry up when
+    // that supply is gone.
+    if level > 0 {
+        let supplied_level = if same_fluid_level(cell + IVec3::Y).is_some() {
+            Some(1)
+        } else {
+            HORIZONTAL_NEIGHBORS
+                .iter()
+                .filter_map(|offset| same_fluid_level(cell + *offset))
+                .min()
+                .map(|neighbor_level| neighbor_level + 1)
+        };
+
+        let Some(supplied_level) = supplied_level.filter(|supplied| *supplied <= max_level) else {
+            return vec![(cell, Voxel::default())];
+        };
+        if supplied_level != level {
+            return vec![(cell, with_level(supplied_level))];
+        }
+    }
+
+    let below = cell + IVec3::NEG_Y;
+    match voxel_at(below) {
+        Some(below_voxel) if below_voxel.block.is_air() => return vec![(below, with_level(1))],
+        Some(below_voxel) if below_voxel.block == voxel.block => return Vec::new(),
+        None => return Vec::new(),
+        Some(_) => {}
+    }
+
+    if level >= max_level {
+        return Vec::new();
+    }
+
+    voxels
+        .neighbors(cell)
+        .filter(|(neighbor, _)| neighbor.y == cell.y)
+        .filter(|(neighbor, neighbor_voxel)| match neighbor_voxel {
+            Some(neighbor_voxel) if neighbor_voxel.block.is_air() => true,
+            Some(_) => same_fluid_level(*neighbor).is_some_and(|other| other > level + 1),
+            None => false,
+        })
+        .map(|(neighbor, _)| (neighbor, with_level(level + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ChunkCoord, World};
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn step(app: &mut App, cell: IVec3) -> Vec<(IVec3, Voxel)> {
+        app.world_mut()
+            .run_system_once(move |voxels: VoxelWorld, blocks: Res<BlockRegistry>| {
+                step_fluid_cell(&voxels, &blocks, cell)
+            })
+            .unwrap()
+    }
+
+    fn app_with_chunk(chunk: Chunk) -> App {
+        let mut app = App::new();
+        app.init_resource::<World>()
+            .init_resource::<BlockRegistry>();
+        let coord = chunk.coord;
+        let entity = app.world_mut().spawn(chunk).id();
+        app.world_mut()
+            .resource_mut::<World>()
+            .chunks
+            .insert(coord, entity);
+        app
+    }
+
+    #[test]
+    fn water_falls_then_spreads_and_dries_up() {
+        let blocks = BlockRegistry::default();
+        let stone = Voxel::new(blocks.id_by_name("stone").unwrap());
+        let water = Voxel::new(blocks.id_by_name("water").unwrap());
+        let flowing = |level| blocks.with_state_index(water, FLUID_LEVEL_PROPERTY, level);
+
+        let mut chunk = Chunk::new(ChunkCoord::new(0, 0, 0));
+        for x in 0..CHUNK_VOXELS_SIZE {
+            for z in 0..CHUNK_VOXELS_SIZE {
+                chunk.set_voxel(x, 0, z, stone);
+            }
+        }
+        chunk.set_voxel(5, 1, 5, water);
+        chunk.set_voxel(9, 3, 9, water);
+        chunk.set_voxel(20, 1, 20, flowing(3));
+        let mut app = app_with_chunk(chunk);
+
+        let mut spread = step(&mut app, IVec3::new(5, 1, 5));
+        spread.sort_by_key(|(cell, _)| (cell.x, cell.z));
+        assert_eq!(
+            spread,
+            vec![
+                (IVec3::new(4, 1, 5), flowing(1)),
+                (IVec3::new(5, 1, 4), flowing(1)),
+                (IVec3::new(5, 1, 6), flowing(1)),
+                (IVec3::new(6, 1, 5), flowing(1)),
+            ]
+        );
+        assert_eq!(
+            step(&mut app, IVec3::new(9, 3, 9)),
+            vec![(IVec3::new(9, 2, 9), flowing(1))]
+        );
+        assert_eq!(
+            step(&mut app, IVec3::new(20, 1, 20)),
+            vec![(IVec3::new(20, 1, 20), Voxel::default())]
+        );
+    }
+}
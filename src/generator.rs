@@ -0,0 +1,339 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::biome::Biome;
+use crate::block::BlockRegistry;
+use crate::terrain::{TerrainGenerator, TerrainSettings};
+use crate::voxel::{BlockId, VOXEL_SIZE, Voxel};
+use crate::world::{CHUNK_VOXELS_SIZE, Chunk, ChunkCoord};
+
+/// Height of the top of the ground in the flat presets, in meters.
+const FLAT_SURFACE_HEIGHT_METERS: f32 = 4.0;
+/// Half the side of the stone platform the void preset spawns the player on, in meters.
+const VOID_PLATFORM_HALF_SIZE_METERS: f32 = 2.0;
+/// Depth of the floor the checkerboard preset draws its tiles on, in meters.
+const CHECKERBOARD_FLOOR_DEPTH_METERS: f32 = 1.0;
+
+/// Fills freshly created chunks with the starting contents of a world.
+pub trait ChunkGenerator: Send + Sync {
+    fn generate_chunk(&self, chunk: &mut Chunk);
+
+    /// Dominant biome at world `x`/`z` in meters, or `None` for worlds without biomes.
+    fn biome_at(&self, _x: f64, _z: f64) -> Option<Biome> {
+        None
+    }
+
+    /// Lowest and highest the ground reaches, in meters, not counting decorations on top.
+    fn surface_bounds(&self) -> (f32, f32) {
+        default_surface_bounds()
+    }
+
+    /// Highest decorations reach above the surface, in meters.
+    fn feature_height_meters(&self) -> f32 {
+        0.0
+    }
+}
+
+/// Surface bounds of the default terrain, which the flat presets also stay within.
+pub fn default_surface_bounds() -> (f32, f32) {
+    let defaults = TerrainSettings::default();
+    (defaults.min_height_meters, defaults.max_height_meters)
+}
+
+impl ChunkGenerator for TerrainGenerator {
+    fn generate_chunk(&self, chunk: &mut Chunk) {
+        TerrainGenerator::generate_chunk(self, chunk);
+    }
+
+    fn biome_at(&self, x: f64, z: f64) -> Option<Biome> {
+        Some(TerrainGenerator::biome_at(self, x, z))
+    }
+
+    fn surface_bounds(&self) -> (f32, f32) {
+        (
+            self.settings().min_height_meters,
+            self.settings().max_height_meters,
+        )
+    }
+
+    fn feature_height_meters(&self) -> f32 {
+        self.settings().decorations.max_height_meters()
+    }
+}
+
+/// Which generator a world uses, chosen when it is created and saved in its metadata.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorldPreset {
+    #[default]
+    Noise,
+    Superflat,
+    Void,
+    Checkerboard,
+}
+
+impl WorldPreset {
+    pub const ALL: [WorldPreset; 4] = [
+        WorldPreset::Noise,
+        WorldPreset::Superflat,
+        WorldPreset::Void,
+        WorldPreset::Checkerboard,
+    ];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            WorldPreset::Noise => "Noise Terrain",
+            WorldPreset::Superflat => "Superflat",
+            WorldPreset::Void => "Void",
+            WorldPreset::Checkerboard => "Debug Checkerboard",
+        }
+    }
+
+    /// Stable identifier written to `world.meta`.
+    pub fn id(self) -> u8 {
+        match self {
+            WorldPreset::Noise => 0,
+            WorldPreset::Superflat => 1,
+            WorldPreset::Void => 2,
+            WorldPreset::Checkerboard => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.id() == id)
+    }
+
+    /// The preset after this one, wrapping around, for cycling through them in the menu.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|preset| *preset == self);
+        Self::ALL[(index.unwrap_or_default() + 1) % Self::ALL.len()]
+    }
+
+    pub fn generator(
+        self,
+        seed: u32,
+        terrain: TerrainSettings,
+        registry: &BlockRegistry,
+    ) -> Arc<dyn ChunkGenerator> {
+        match self {
+            WorldPreset::Noise => Arc::new(TerrainGenerator::new(seed, registry, terrain)),
+            WorldPreset::Superflat => Arc::new(SuperflatGenerator::new(registry)),
+            WorldPreset::Void => Arc::new(VoidGenerator::new(registry)),
+            WorldPreset::Checkerboard => Arc::new(CheckerboardGenerator::new(registry)),
+        }
+    }
+}
+
+/// Global row and block of the topmost non-air voxel of every column in a chunk column, indexed
+/// `x + z * CHUNK_VOXELS_SIZE`, or `None` for columns that stay empty. Chunks are generated from
+/// the tallest possible decoration down to just below the lowest terrain surface, stopping as
+/// soon as every column has been found.
+pub fn column_tops(
+    generator: &dyn ChunkGenerator,
+    chunk_x: i32,
+    chunk_z: i32,
+) -> Vec<Option<(i32, BlockId)>> {
+    let size = CHUNK_VOXELS_SIZE;
+    let (lowest, highest) = generator.surface_bounds();
+    let top_cell = ((highest + generator.feature_height_meters()) / VOXEL_SIZE) as i32;
+    let bottom_cell = (lowest / VOXEL_SIZE).floor() as i32 - 1;
+    let top_chunk = top_cell.div_euclid(size as i32);
+    let bottom_chunk = bottom_cell.div_euclid(size as i32);
+
+    let mut tops = vec![None; size * size];
+    for chunk_y in (bottom_chunk..=top_chunk).rev() {
+        let mut chunk = Chunk::new(ChunkCoord::new(chunk_x, chunk_y, chunk_z));
+        generator.generate_chunk(&mut chunk);
+        let origin_y = chunk.coord.origin_cell().y;
+        for (index, top) in tops.iter_mut().enumerate() {
+            if top.is_some() {
+                continue;
+            }
+            *top = (0..size).rev().find_map(|y| {
+                let voxel = chunk.get_voxel(index % size, y, index / size)?;
+                (!voxel.block.is_air()).then_some((origin_y + y as i32, voxel.block))
+            });
+        }
+        if tops.iter().all(Option::is_some) {
+            break;
+        }
+    }
+    tops
+}
+
+/// Global voxel row of the topmost ground voxel in the flat presets.
+fn flat_surface_cell_y() -> i32 {
+    (FLAT_SURFACE_HEIGHT_METERS / VOXEL_SIZE).round() as i32 - 1
+}
+
+/// Calls `fill` for every voxel of the chunk with its global cell.
+fn fill_cells(chunk: &mut Chunk, mut fill: impl FnMut(IVec3) -> Option<BlockId>) {
+    let origin = chunk.coord.origin_cell();
+    for x in 0..CHUNK_VOXELS_SIZE {
+        for y in 0..CHUNK_VOXELS_SIZE {
+            for z in 0..CHUNK_VOXELS_SIZE {
+                let cell = origin + IVec3::new(x as i32, y as i32, z as i32);
+                if let Some(block) = fill(cell) {
+                    chunk.set_voxel(x, y, z, Voxel::new(block));
+                }
+            }
+        }
+    }
+}
+
+/// Layers of grass, dirt and stone over a flat world, each given as block and thickness in
+/// voxels from the top down. Everything below the last layer is stone.
+pub struct SuperflatGenerator {
+    layers: Vec<(BlockId, i32)>,
+    stone: BlockId,
+}
+
+impl SuperflatGenerator {
+    pub fn new(registry: &BlockRegistry) -> Self {
+        let id = |name: &str| registry.generated_block(name);
+        Self {
+            layers: vec![(id("grass"), 1), (id("dirt"), 12)],
+            stone: id("stone"),
+        }
+    }
+
+    fn block_at_depth(&self, depth: i32) -> BlockId {
+        let mut layer_bottom = 0;
+        for (block, thickness) in &self.layers {
+            layer_bottom += thickness;
+            if depth < layer_bottom {
+                return *block;
+            }
+        }
+        self.stone
+    }
+}
+
+impl ChunkGenerator for SuperflatGenerator {
+    fn generate_chunk(&self, chunk: &mut Chunk) {
+        let surface_y = flat_surface_cell_y();
+        if chunk.coord.origin_cell().y > surface_y {
+            return;
+        }
+        fill_cells(chunk, |cell| {
+            (cell.y <= surface_y).then(|| self.block_at_depth(surface_y - cell.y))
+        });
+    }
+}
+
+/// Nothing but a small stone platform at the spawn point.
+pub struct VoidGenerator {
+    stone: BlockId,
+}
+
+impl VoidGenerator {
+    pub fn new(registry: &BlockRegistry) -> Self {
+        Self {
+            stone: registry.generated_block("stone"),
+        }
+    }
+}
+
+impl ChunkGenerator for VoidGenerator {
+    fn generate_chunk(&self, chunk: &mut Chunk) {
+        let surface_y = flat_surface_cell_y();
+        let half_size = (VOID_PLATFORM_HALF_SIZE_METERS / VOXEL_SIZE).round() as i32;
+        let platform_min = IVec3::new(-half_size, surface_y, -half_size);
+        let platform_max = IVec3::new(half_size - 1, surface_y, half_size - 1);
+
+        let chunk_min = chunk.coord.origin_cell();
+        let chunk_max = chunk_min + IVec3::splat(CHUNK_VOXELS_SIZE as i32 - 1);
+        if platform_min.cmpgt(chunk_max).any() || platform_max.cmplt(chunk_min).any() {
+            return;
+        }
+        fill_cells(chunk, |cell| {
+            (cell.cmpge(platform_min).all() && cell.cmple(platform_max).all()).then_some(self.stone)
+        });
+    }
+}
+
+/// A flat floor tiled with alternating blocks, one tile per chunk, so chunk borders and
+/// coordinates are easy to read.
+pub struct CheckerboardGenerator {
+    tiles: [BlockId; 2],
+}
+
+impl CheckerboardGenerator {
+    pub fn new(registry: &BlockRegistry) -> Self {
+        let id = |name: &str| registry.generated_block(name);
+        Self {
+            tiles: [id("stone"), id("sand")],
+        }
+    }
+}
+
+impl ChunkGenerator for CheckerboardGenerator {
+    fn generate_chunk(&self, chunk: &mut Chunk) {
+        let surface_y = flat_surface_cell_y();
+        let floor_bottom =
+            surface_y - (CHECKERBOARD_FLOOR_DEPTH_METERS / VOXEL_SIZE).round() as i32 + 1;
+        let tile = self.tiles[(chunk.coord.x + chunk.coord.z).rem_euclid(2) as usize];
+        fill_cells(chunk, |cell| {
+            (floor_bottom..=surface_y).contains(&cell.y).then_some(tile)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::split_cell;
+
+    fn block_at(generator: &dyn ChunkGenerator, cell: IVec3) -> BlockId {
+        let (coord, x, y, z) = split_cell(cell);
+        let mut chunk = Chunk::new(coord);
+        generator.generate_chunk(&mut chunk);
+        chunk.get_voxel(x, y, z).unwrap().block
+    }
+
+    #[test]
+    fn presets_generate_their_layouts_and_round_trip_ids() {
+        let registry = BlockRegistry::default();
+        let id = |name| registry.id_by_name(name).unwrap();
+        let surface_y = flat_surface_cell_y();
+
+        for preset in WorldPreset::ALL {
+            assert_eq!(WorldPreset::from_id(preset.id()), Some(preset));
+        }
+        assert_eq!(WorldPreset::Checkerboard.next(), WorldPreset::Noise);
+
+        let superflat = WorldPreset::Superflat.generator(1, TerrainSettings::default(), &registry);
+        assert_eq!(
+            block_at(&*superflat, IVec3::new(-70, surface_y, 9)),
+            id("grass")
+        );
+        assert_eq!(
+            block_at(&*superflat, IVec3::new(-70, surface_y - 1, 9)),
+            id("dirt")
+        );
+        assert_eq!(
+            block_at(&*superflat, IVec3::new(-70, surface_y - 40, 9)),
+            id("stone")
+        );
+        assert!(block_at(&*superflat, IVec3::new(-70, surface_y + 1, 9)).is_air());
+
+        let void = WorldPreset::Void.generator(1, TerrainSettings::default(), &registry);
+        assert_eq!(block_at(&*void, IVec3::new(0, surface_y, 0)), id("stone"));
+        assert!(block_at(&*void, IVec3::new(0, surface_y - 1, 0)).is_air());
+        assert!(block_at(&*void, IVec3::new(500, surface_y, 0)).is_air());
+
+        let checkerboard =
+            WorldPreset::Checkerboard.generator(1, TerrainSettings::default(), &registry);
+        let size = CHUNK_VOXELS_SIZE as i32;
+        let tile =
+            |chunk_x: i32| block_at(&*checkerboard, IVec3::new(chunk_x * size, surface_y, -size));
+        assert_ne!(tile(-1), tile(0));
+        assert_eq!(tile(-1), tile(1));
+
+        let noise = WorldPreset::Noise.generator(1, TerrainSettings::default(), &registry);
+        assert!(noise.biome_at(3.0, -7.0).is_some());
+        for flat in [&superflat, &void, &checkerboard] {
+            assert_eq!(flat.biome_at(3.0, -7.0), None);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::block::BlockRegistry;
+use crate::world::{ViewDistanceSettings, VoxelAccess, VoxelChanged, VoxelChanges, VoxelWorld};
+
+pub const FALL_STEP_SECONDS: f32 = 0.03;
+
+/// Cells whose falling block may have lost its support since the last step.
+#[derive(Resource)]
+pub struct FallingBlocks {
+    timer: Timer,
+    pending: HashSet<IVec3>,
+}
+
+impl Default for FallingBlocks {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(FALL_STEP_SECONDS, TimerMode::Repeating),
+            pending: HashSet::default(),
+        }
+    }
+}
+
+pub struct GravityPlugin;
+
+impl Plugin for GravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FallingBlocks>()
+            .add_systems(OnEnter(AppState::LoadingWorld), reset_falling_blocks)
+            .add_systems(
+                Update,
+                (collect_unsupported_blocks, fall_step_system)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn reset_falling_blocks(mut falling: ResMut<FallingBlocks>) {
+    *falling = FallingBlocks::default();
+}
+
+/// A change can drop the block in the changed cell itself or the block resting on top of it.
+fn collect_unsupported_blocks(
+    mut falling: ResMut<FallingBlocks>,
+    mut voxel_changes: MessageReader<VoxelChanged>,
+) {
+    for change in voxel_changes.read() {
+        falling.pending.insert(change.cell);
+        falling.pending.insert(change.cell + IVec3::Y);
+    }
+}
+
+/// Moves every unsupported falling block down by one voxel. The moves are reported as
+/// `VoxelChanged`, which re-queues the block until it lands and wakes whatever was above it.
+fn fall_step_system(
+    time: Res<Time>,
+    mut falling: ResMut<FallingBlocks>,
+    mut voxels: VoxelWorld,
+    mut changes: VoxelChanges,
+    blocks: Res<BlockRegistry>,
+    view: Res<ViewDistanceSettings>,
+) {
+    falling.timer.tick(time.delta());
+    if !falling.timer.just_finished() {
+        return;
+    }
+
+    // Blocks beyond the simulation distance wait in place until the player comes closer.
+    let (paused, mut cells): (Vec<_>, Vec<_>) = falling
+        .pending
+        .drain()
+        .partition(|cell| voxels.world().simulation_paused(*cell, &view));
+    falling.pending.extend(paused);
+    // Lower blocks first so a whole stack moves down together.
+    cells.sort_by_key(|cell| (cell.y, cell.x, cell.z));
+
+    for cell in cells {
+        let Some(voxel) = voxels.get(cell) else {
+            continue;
+        };
+        // Whether a block over an unloaded chunk is supported is only known once that chunk loads.
+        if blocks.falls(voxel.block) && !voxels.is_loaded(cell + IVec3::NEG_Y) {
+            falling.pending.insert(cell);
+            continue;
+        }
+        let Some(below) = fall_target(&voxels, &blocks, cell) else {
+            continue;
+        };
+
+        // A fluid in the way swaps places with the block, so falling into water loses none of it.
+        let displaced = voxels.get(below).unwrap_or_default();
+        let moved = voxels.set(below, voxel) && voxels.set(cell, displaced);
+        if !moved {
+            continue;
+        }
+
+        for changed_cell in [cell, below] {
+            changes.report(voxels.world(), changed_cell);
+        }
+    }
+}
+
+/// Returns the cell a falling block drops into, if the voxel below it is loaded and not solid.
+fn fall_target(voxels: &VoxelWorld, blocks: &BlockRegistry, cell: IVec3) -> Option<IVec3> {
+    let voxel = voxels.get(cell)?;
+    if !blocks.falls(voxel.block) {
+        return None;
+    }
+
+    let below = cell + IVec3::NEG_Y;
+    let below_voxel = voxels.get(below)?;
+    (!blocks.is_solid(below_voxel.block)).then_some(below)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::save::SaveState;
+    use crate::voxel::Voxel;
+    use crate::world::{Chunk, ChunkCoord, World};
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+
+    #[test]
+    fn sand_falls_across_chunk_borders_but_rests_on_solid_ground() {
+        let blocks = BlockRegistry::default();
+        let sand = Voxel::new(blocks.id_by_name("sand").unwrap());
+        let stone = Voxel::new(blocks.id_by_name("stone").unwrap());
+
+        let mut upper = Chunk::new(ChunkCoord::new(0, 0, 0));
+        upper.set_voxel(3, 0, 3, sand);
+        upper.set_voxel(6, 1, 6, sand);
+        upper.set_voxel(6, 0, 6, stone);
+        let lower = Chunk::new(ChunkCoord::new(0, -1, 0));
+
+        let mut app = App::new();
+        app.init_resource::<World>()
+            .init_resource::<BlockRegistry>();
+        for chunk in [upper, lower] {
+            let coord = chunk.coord;
+            let entity = app.world_mut().spawn(chunk).id();
+            app.world_mut()
+                .resource_mut::<World>()
+                .chunks
+                .insert(coord, entity);
+        }
+
+        let targets = app
+            .world_mut()
+            .run_system_once(|voxels: VoxelWorld, blocks: Res<BlockRegistry>| {
+                [
+                    IVec3::new(3, 0, 3),
+                    IVec3::new(6, 1, 6),
+                    IVec3::new(6, 0, 6),
+                ]
+                .map(|cell| fall_target(&voxels, &blocks, cell))
+            })
+            .unwrap();
+
+        assert_eq!(targets, [Some(IVec3::new(3, -1, 3)), None, None]);
+    }
+
+    /// Returns an app running the gravity systems in game over the given chunks.
+    fn gravity_app(chunks: impl IntoIterator<Item = Chunk>) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(StatesPlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                FALL_STEP_SECONDS,
+            )))
+            .init_state::<AppState>()
+            .init_resource::<World>()
+            .init_resource::<BlockRegistry>()
+            .init_resource::<SaveState>()
+            .init_resource::<ViewDistanceSettings>()
+            .add_message::<VoxelChanged>()
+            .add_plugins(GravityPlugin);
+        for chunk in chunks {
+            insert_chunk(&mut app, chunk);
+        }
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+        app.update();
+        app
+    }
+
+    fn insert_chunk(app: &mut App, chunk: Chunk) {
+        let coord = chunk.coord;
+        let entity = app.world_mut().spawn(chunk).id();
+        app.world_mut()
+            .resource_mut::<World>()
+            .chunks
+            .insert(coord, entity);
+    }
+
+    fn column(app: &mut App, x: i32, z: i32, ys: std::ops::Range<i32>) -> Vec<Voxel> {
+        app.world_mut()
+            .run_system_once(move |voxels: VoxelWorld| {
+                ys.clone()
+                    .map(|y| voxels.get(IVec3::new(x, y, z)).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn stacked_column_lands_intact_below_a_chunk_border() {
+        let blocks = BlockRegistry::default();
+        let sand = Voxel::new(blocks.id_by_name("sand").unwrap());
+        let gravel = Voxel::new(blocks.id_by_name("gravel").unwrap());
+        let stone = Voxel::new(blocks.id_by_name("stone").unwrap());
+        let stack = [sand, gravel, sand, gravel];
+
+        let mut upper = Chunk::new(ChunkCoord::new(0, 0, 0));
+        for (y, voxel) in stack.into_iter().enumerate() {
+            upper.set_voxel(3, y, 3, voxel);
+        }
+        let mut lower = Chunk::new(ChunkCoord::new(0, -1, 0));
+        lower.set_voxel(3, 20, 3, stone);
+        let mut app = gravity_app([upper, lower]);
+
+        // Knocking out the support of the bottom block is what sets the column moving.
+        app.world_mut().write_message(VoxelChanged {
+            cell: IVec3::new(3, 0, 3),
+        });
+        for _ in 0..64 {
+            app.update();
+        }
+
+        let mut expected = vec![Voxel::default(); 16];
+        expected[0] = stone;
+        expected[1..5].copy_from_slice(&stack);
+        assert_eq!(column(&mut app, 3, 3, -12..4), expected);
+        assert!(app.world().resource::<SaveState>().dirty);
+    }
+
+    #[test]
+    fn falling_into_a_fluid_column_keeps_the_fluid() {
+        let blocks = BlockRegistry::default();
+        let sand = Voxel::new(blocks.id_by_name("sand").unwrap());
+        let stone = Voxel::new(blocks.id_by_name("stone").unwrap());
+        let water = Voxel::new(blocks.id_by_name("water").unwrap());
+
+        let mut chunk = Chunk::new(ChunkCoord::new(0, 0, 0));
+        for (y, voxel) in [stone, water, water, water, sand].into_iter().enumerate() {
+            chunk.set_voxel(5, y, 5, voxel);
+        }
+        let mut app = gravity_app([chunk]);
+
+        app.world_mut().write_message(VoxelChanged {
+            cell: IVec3::new(5, 3, 5),
+        });
+        for _ in 0..16 {
+            app.update();
+        }
+
+        assert_eq!(
+            column(&mut app, 5, 5, 0..5),
+            vec![stone, sand, water, water, water]
+        );
+    }
+
+    #[test]
+    fn blocks_over_an_unloaded_chunk_fall_once_it_loads() {
+        let blocks = BlockRegistry::default();
+        let sand = Voxel::new(blocks.id_by_name("sand").unwrap());
+
+        let mut upper = Chunk::new(ChunkCoord::new(0, 0, 0));
+        upper.set_voxel(3, 0, 3, sand);
+        let mut app = gravity_app([upper]);
+
+        app.world_mut().write_message(VoxelChanged {
+            cell: IVec3::new(3, 0, 3),
+        });
+        for _ in 0..4 {
+            app.update();
+        }
+        assert_eq!(column(&mut app, 3, 3, 0..1), vec![sand]);
+
+        insert_chunk(&mut app, Chunk::new(ChunkCoord::new(0, -1, 0)));
+        for _ in 0..4 {
+            app.update();
+        }
+        assert_eq!(column(&mut app, 3, 3, 0..1), vec![Voxel::default()]);
+    }
+}
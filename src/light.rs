@@ -0,0 +1,579 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::block::BlockRegistry;
+use crate::player::NeedsRenderRefresh;
+use crate::voxel::Voxel;
+use crate::world::{
+    CHUNK_VOXELS_SIZE, Chunk, ChunkCoord, FACE_NEIGHBORS, VoxelAccess, VoxelChanged, VoxelWorld,
+    chunk_world_origin, chunks_sharing_voxel, split_cell,
+};
+
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+/// Extra brightness of a face at full block light, relative to its unlit color.
+const BLOCK_LIGHT_BOOST: f32 = 1.5;
+/// Brightness of a face that no sky light reaches, so unlit caves stay faintly visible.
+const MIN_SKY_BRIGHTNESS: f32 = 0.08;
+
+/// One of the two independent light values every voxel carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightChannel {
+    /// Light flood-filled from emissive blocks such as lamps and lava.
+    Block,
+    /// Daylight that falls down open columns at full strength and fades as it spreads sideways.
+    Sky,
+}
+
+impl LightChannel {
+    pub const ALL: [LightChannel; 2] = [LightChannel::Block, LightChannel::Sky];
+}
+
+/// Light levels of one channel across a chunk. A chunk where every voxel has the same level keeps
+/// no per-voxel storage, and clones share the levels until one of them is written to.
+#[derive(Clone, Debug)]
+pub struct LightLevels {
+    len: usize,
+    fill: u8,
+    levels: Option<Arc<Vec<u8>>>,
+}
+
+impl LightLevels {
+    pub fn uniform(len: usize, level: u8) -> Self {
+        Self {
+            len,
+            fill: level,
+            levels: None,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        self.levels
+            .as_ref()
+            .and_then(|levels| levels.get(index).copied())
+            .unwrap_or(self.fill)
+    }
+
+    /// Writes a level and returns whether the stored value changed.
+    pub fn set(&mut self, index: usize, level: u8) -> bool {
+        if index >= self.len || self.get(index) == level {
+            return false;
+        }
+
+        let (len, fill) = (self.len, self.fill);
+        let levels = self.levels.get_or_insert_with(|| Arc::new(vec![fill; len]));
+        Arc::make_mut(levels)[index] = level;
+        true
+    }
+
+    pub fn uniform_level(&self) -> Option<u8> {
+        self.levels.is_none().then_some(self.fill)
+    }
+
+    pub fn heap_size(&self) -> usize {
+        self.levels.as_ref().map_or(0, |levels| levels.len())
+    }
+}
+
+pub struct LightPlugin;
+
+impl Plugin for LightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (light_loaded_chunks, relight_changed_voxels)
+                .chain()
+                .run_if(in_state(AppState::LoadingWorld).or(in_state(AppState::InGame))),
+        );
+    }
+}
+
+/// Factor a face's color is scaled by, given the light of the voxel in front of it. Sky light
+/// fades the face from its full color down to near black, and block light brightens it on top.
+pub fn light_brightness(block_light: u8, sky_light: u8) -> f32 {
+    let falloff = |level: u8| {
+        let fraction = f32::from(level.min(MAX_LIGHT_LEVEL)) / f32::from(MAX_LIGHT_LEVEL);
+        fraction * fraction
+    };
+    MIN_SKY_BRIGHTNESS
+        + (1.0 - MIN_SKY_BRIGHTNESS) * falloff(sky_light)
+        + BLOCK_LIGHT_BOOST * falloff(block_light)
+}
+
+/// Reads and writes one light channel across the loaded chunks, collecting the chunks whose
+/// meshes need the new light baked in.
+struct LightGrid<'a, 'w, 's> {
+    voxels: &'a mut VoxelWorld<'w, 's>,
+    blocks: &'a BlockRegistry,
+    channel: LightChannel,
+    dirty_chunks: HashSet<ChunkCoord>,
+}
+
+impl<'a, 'w, 's> LightGrid<'a, 'w, 's> {
+    fn new(
+        voxels: &'a mut VoxelWorld<'w, 's>,
+        blocks: &'a BlockRegistry,
+        channel: LightChannel,
+    ) -> Self {
+        Self {
+            voxels,
+            blocks,
+            channel,
+            dirty_chunks: HashSet::default(),
+        }
+    }
+
+    fn chunk(&self, coord: ChunkCoord) -> Option<&Chunk> {
+        self.voxels.chunk(coord)
+    }
+
+    /// Unloaded chunks above the highest generated terrain count as open sky, so the topmost
+    /// loaded chunks of a column receive daylight before anything above them streams in.
+    fn is_open_sky(&self, coord: ChunkCoord) -> bool {
+        chunk_world_origin(coord).y >= self.voxels.world().surface_bounds().1
+    }
+
+    /// Light level of a loaded voxel.
+    fn light(&self, cell: IVec3) -> Option<u8> {
+        let (coord, x, y, z) = split_cell(cell);
+        Some(self.chunk(coord)?.light(self.channel, x, y, z))
+    }
+
+    fn set_light(&mut self, cell: IVec3, level: u8) {
+        let (coord, x, y, z) = split_cell(cell);
+        if let Some(mut chunk) = self.voxels.chunk_mut(coord)
+            && chunk.set_light(self.channel, x, y, z, level)
+        {
+            self.dirty_chunks
+                .extend(chunks_sharing_voxel(coord, x, y, z));
+        }
+    }
+
+    fn voxel(&self, cell: IVec3) -> Option<Voxel> {
+        self.voxels.get(cell)
+    }
+
+    /// Level a voxel has on its own, before any light spreads into it: the emission of its block,
+    /// or full sky light for a transparent voxel right below open sky.
+    fn source(&self, cell: IVec3) -> u8 {
+        let Some(voxel) = self.voxel(cell) else {
+            return 0;
+        };
+
+        match self.channel {
+            LightChannel::Block => self.blocks.light_emission(voxel.block),
+            LightChannel::Sky => {
+                let above = cell + IVec3::Y;
+                let open = !self.blocks.is_opaque(voxel.block)
+                    && !self.voxels.is_loaded(above)
+                    && self.is_open_sky(ChunkCoord::from_cell(above));
+                if open { MAX_LIGHT_LEVEL } else { 0 }
+            }
+        }
+    }
+
+    /// Light passes through every loaded voxel that is not opaque.
+    fn transmits(&self, cell: IVec3) -> bool {
+        self.voxel(cell)
+            .is_some_and(|voxel| !self.blocks.is_opaque(voxel.block))
+    }
+
+    /// Level that light of `level` reaches the neighbor at `offset` with. Full sky light falls
+    /// straight down without fading; everything else loses one level per voxel.
+    fn spread_level(&self, level: u8, offset: IVec3) -> u8 {
+        if self.channel == LightChannel::Sky && offset == IVec3::NEG_Y && level == MAX_LIGHT_LEVEL {
+            MAX_LIGHT_LEVEL
+        } else {
+            level.saturating_sub(1)
+        }
+    }
+
+    /// Lights a newly loaded chunk from its own sources and returns the cells to spread from,
+    /// including lit cells of already loaded neighbors that border it.
+    fn seed_chunk(&mut self, coord: ChunkCoord) -> Vec<IVec3> {
+        let Some(chunk) = self.chunk(coord) else {
+            return Vec::new();
+        };
+        let chunk_base = coord.origin_cell();
+        let mut seeds = Vec::new();
+
+        let mut sources = Vec::new();
+        match self.channel {
+            LightChannel::Block => {
+                if chunk.may_contain(|voxel| self.blocks.light_emission(voxel.block) > 0) {
+                    for x in 0..CHUNK_VOXELS_SIZE {
+                        for y in 0..CHUNK_VOXELS_SIZE {
+                            for z in 0..CHUNK_VOXELS_SIZE {
+                                let cell = chunk_base + IVec3::new(x as i32, y as i32, z as i32);
+                                sources.push(cell);
+                            }
+                        }
+                    }
+                }
+            }
+            LightChannel::Sky => {
+                let above = coord.offset(0, 1, 0);
+                let open_above = match self.chunk(above) {
+                    Some(above_chunk) => {
+                        above_chunk.uniform_light(LightChannel::Sky) == Some(MAX_LIGHT_LEVEL)
+                    }
+                    None => self.is_open_sky(above),
+                };
+
+                // Air under open sky is lit throughout, so skip flooding it voxel by voxel and
+                // only spread from its faces into the neighbors.
+                if chunk.is_empty() && open_above {
+                    self.fill_chunk(coord, MAX_LIGHT_LEVEL);
+                    for direction in FACE_NEIGHBORS {
+                        let neighbor = coord.offset(direction.x, direction.y, direction.z);
+                        if self.chunk(neighbor).is_some() {
+                            seeds.extend(face_cells(coord, direction));
+                        }
+                    }
+                    return seeds;
+                }
+
+                sources.extend(face_cells(coord, IVec3::Y));
+            }
+        }
+        for cell in sources {
+            let level = self.source(cell);
+            if level > 0 {
+                self.set_light(cell, level);
+                seeds.push(cell);
+            }
+        }
+
+        for direction in FACE_NEIGHBORS {
+            let neighbor = coord.offset(direction.x, direction.y, direction.z);
+            if self
+                .chunk(neighbor)
+                .is_none_or(|chunk| chunk.uniform_light(self.channel) == Some(0))
+            {
+                continue;
+            }
+
+            for cell in face_cells(coord, direction) {
+                let across = cell + direction;
+                if self.light(across).is_some_and(|level| level > 0) {
+                    seeds.push(across);
+                }
+            }
+        }
+
+        seeds
+    }
+
+    fn fill_chunk(&mut self, coord: ChunkCoord, level: u8) {
+        if let Some(mut chunk) = self.voxels.chunk_mut(coord) {
+            chunk.fill_light(self.channel, level);
+            self.dirty_chunks.insert(coord);
+            for direction in FACE_NEIGHBORS {
+                self.dirty_chunks
+                    .insert(coord.offset(direction.x, direction.y, direction.z));
+            }
+        }
+    }
+
+    /// Lights newly loaded chunks and updates the loaded chunks around them.
+    fn light_chunks(&mut self, coords: &[ChunkCoord]) {
+        let mut queue = VecDeque::new();
+        for coord in coords {
+            queue.extend(self.seed_chunk(*coord));
+        }
+        self.spread(queue);
+
+        if self.channel != LightChannel::Sky {
+            return;
+        }
+
+        // The chunk below may have been lit as if under open sky before this one loaded on top of
+        // it, so relight the columns that are no longer open.
+        for coord in coords {
+            for cell in face_cells(coord.offset(0, -1, 0), IVec3::Y) {
+                if self.light(cell) == Some(MAX_LIGHT_LEVEL)
+                    && self.light(cell + IVec3::Y) != Some(MAX_LIGHT_LEVEL)
+                {
+                    self.relight_cell(cell);
+                }
+            }
+        }
+    }
+
+    /// Spreads light outward from each queued cell, raising every neighbor darker than the level
+    /// the cell's light reaches it with.
+    fn spread(&mut self, mut queue: VecDeque<IVec3>) {
+        while let Some(cell) = queue.pop_front() {
+            let Some(level) = self.light(cell).filter(|level| *level > 1) else {
+                continue;
+            };
+
+            for offset in FACE_NEIGHBORS {
+                let neighbor = cell + offset;
+                let reached = self.spread_level(level, offset);
+                if self.transmits(neighbor)
+                    && self.light(neighbor).is_some_and(|other| other < reached)
+                {
+                    self.set_light(neighbor, reached);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    /// Darkens the light that flowed out of the queued cells and returns the lit cells around the
+    /// darkened area, which have to spread their light back into it.
+    fn remove(&mut self, mut queue: VecDeque<(IVec3, u8)>) -> VecDeque<IVec3> {
+        let mut relight = VecDeque::new();
+
+        while let Some((cell, level)) = queue.pop_front() {
+            for offset in FACE_NEIGHBORS {
+                let neighbor = cell + offset;
+                let Some(neighbor_level) = self.light(neighbor).filter(|level| *level > 0) else {
+                    continue;
+                };
+
+                if neighbor_level <= self.spread_level(level, offset) {
+                    self.set_light(neighbor, 0);
+                    queue.push_back((neighbor, neighbor_level));
+
+                    let source = self.source(neighbor);
+                    if source > 0 {
+                        self.set_light(neighbor, source);
+                        relight.push_back(neighbor);
+                    }
+                } else {
+                    relight.push_back(neighbor);
+                }
+            }
+        }
+
+        relight
+    }
+
+    /// Recomputes light around a voxel whose block changed.
+    fn relight_cell(&mut self, cell: IVec3) {
+        let Some(previous) = self.light(cell) else {
+            return;
+        };
+
+        self.set_light(cell, 0);
+        let mut relight = self.remove(VecDeque::from([(cell, previous)]));
+        let source = self.source(cell);
+        if source > 0 {
+            self.set_light(cell, source);
+            relight.push_back(cell);
+        }
+        self.spread(relight);
+    }
+
+    fn mark_dirty_chunks(self, commands: &mut Commands) {
+        for coord in self.dirty_chunks {
+            if let Some(entity) = self.voxels.world().chunks.get(&coord) {
+                commands.entity(*entity).insert(NeedsRenderRefresh);
+            }
+        }
+    }
+}
+
+/// Global cells of a chunk's face on the side of `direction`.
+fn face_cells(coord: ChunkCoord, direction: IVec3) -> impl Iterator<Item = IVec3> {
+    let chunk_base = coord.origin_cell();
+    let last = CHUNK_VOXELS_SIZE as i32 - 1;
+    let axis = direction.abs().max_position();
+    let fixed = if direction[axis] > 0 { last } else { 0 };
+
+    (0..=last).flat_map(move |u| {
+        (0..=last).map(move |v| {
+            let mut local = IVec3::ZERO;
+            local[axis] = fixed;
+            local[(axis + 1) % 3] = u;
+            local[(axis + 2) % 3] = v;
+            chunk_base + local
+        })
+    })
+}
+
+/// Lights newly loaded chunks from their own sources and from the light of their neighbors.
+fn light_loaded_chunks(
+    mut commands: Commands,
+    mut params: ParamSet<(Query<&Chunk, Added<Chunk>>, VoxelWorld)>,
+    blocks: Res<BlockRegistry>,
+) {
+    let added = params
+        .p0()
+        .iter()
+        .map(|chunk| chunk.coord)
+        .collect::<Vec<_>>();
+    if added.is_empty() {
+        return;
+    }
+
+    let mut voxels = params.p1();
+    for channel in LightChannel::ALL {
+        let mut grid = LightGrid::new(&mut voxels, &blocks, channel);
+        grid.light_chunks(&added);
+        grid.mark_dirty_chunks(&mut commands);
+    }
+}
+
+/// Updates light incrementally around every changed voxel, across chunk borders, so opening or
+/// closing a column lets daylight in or shuts it out.
+fn relight_changed_voxels(
+    mut commands: Commands,
+    mut voxels: VoxelWorld,
+    blocks: Res<BlockRegistry>,
+    mut voxel_changes: MessageReader<VoxelChanged>,
+) {
+    let changed_cells = voxel_changes
+        .read()
+        .map(|change| change.cell)
+        .collect::<Vec<_>>();
+    if changed_cells.is_empty() {
+        return;
+    }
+
+    for channel in LightChannel::ALL {
+        let mut grid = LightGrid::new(&mut voxels, &blocks, channel);
+        for cell in &changed_cells {
+            grid.relight_cell(*cell);
+        }
+        grid.mark_dirty_chunks(&mut commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::World;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn app_with_chunks(coords: &[ChunkCoord]) -> App {
+        let mut app = App::new();
+        app.init_resource::<World>()
+            .init_resource::<BlockRegistry>();
+        for coord in coords {
+            let entity = app.world_mut().spawn(Chunk::new(*coord)).id();
+            app.world_mut()
+                .resource_mut::<World>()
+                .chunks
+                .insert(*coord, entity);
+        }
+        app
+    }
+
+    fn light_at(app: &mut App, channel: LightChannel, cell: IVec3) -> Option<u8> {
+        app.world_mut()
+            .run_system_once(move |mut voxels: VoxelWorld, blocks: Res<BlockRegistry>| {
+                LightGrid::new(&mut voxels, &blocks, channel).light(cell)
+            })
+            .unwrap()
+    }
+
+    fn set_voxel(app: &mut App, cell: IVec3, voxel: Voxel) {
+        app.world_mut()
+            .run_system_once(move |mut voxels: VoxelWorld, blocks: Res<BlockRegistry>| {
+                voxels.set(cell, voxel);
+                for channel in LightChannel::ALL {
+                    LightGrid::new(&mut voxels, &blocks, channel).relight_cell(cell);
+                }
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn lamp_light_crosses_chunk_borders_and_goes_out_when_removed() {
+        let blocks = BlockRegistry::default();
+        let lamp = Voxel::new(blocks.id_by_name("lamp").unwrap());
+        let stone = Voxel::new(blocks.id_by_name("stone").unwrap());
+
+        let mut app = app_with_chunks(&[ChunkCoord::new(0, 0, 0), ChunkCoord::new(-1, 0, 0)]);
+        let block_light = |app: &mut App, cell| light_at(app, LightChannel::Block, cell);
+
+        let lamp_cell = IVec3::new(1, 4, 4);
+        set_voxel(&mut app, lamp_cell, lamp);
+        assert_eq!(block_light(&mut app, lamp_cell), Some(MAX_LIGHT_LEVEL));
+        assert_eq!(block_light(&mut app, IVec3::new(-1, 4, 4)), Some(13));
+        assert_eq!(block_light(&mut app, IVec3::new(1, 4, 10)), Some(9));
+
+        // A wall next to the lamp blocks it, so light has to walk around.
+        set_voxel(&mut app, IVec3::new(0, 4, 4), stone);
+        assert_eq!(block_light(&mut app, IVec3::new(0, 4, 4)), Some(0));
+        assert_eq!(block_light(&mut app, IVec3::new(-1, 4, 4)), Some(11));
+
+        set_voxel(&mut app, lamp_cell, Voxel::default());
+        assert_eq!(block_light(&mut app, lamp_cell), Some(0));
+        assert_eq!(block_light(&mut app, IVec3::new(-1, 4, 4)), Some(0));
+        assert_eq!(block_light(&mut app, IVec3::new(1, 4, 10)), Some(0));
+    }
+
+    #[test]
+    fn sky_light_falls_down_open_columns_and_is_shut_out_by_a_roof() {
+        let blocks = BlockRegistry::default();
+        let stone = Voxel::new(blocks.id_by_name("stone").unwrap());
+        let sky_light = |app: &mut App, cell| light_at(app, LightChannel::Sky, cell);
+
+        // Chunks from y = 5 up lie above the terrain, so the unloaded one on top counts as open sky.
+        let top = ChunkCoord::new(0, 5, 0);
+        let bottom = ChunkCoord::new(0, 4, 0);
+        let mut app = app_with_chunks(&[top, bottom]);
+        let floor_y = bottom.y * CHUNK_VOXELS_SIZE as i32;
+        app.world_mut()
+            .run_system_once(move |mut chunks: Query<&mut Chunk>| {
+                for mut chunk in chunks.iter_mut() {
+                    if chunk.coord == top {
+                        // A roof over the corner of the column.
+                        for x in 0..8 {
+                            for z in 0..8 {
+                                chunk.set_voxel(x, 4, z, stone);
+                            }
+                        }
+                    }
+                }
+            })
+            .unwrap();
+        let revisions = |app: &mut App| {
+            app.world_mut()
+                .query::<&Chunk>()
+                .iter(app.world())
+                .map(|chunk| (chunk.revision, chunk.light_revision))
+                .collect::<Vec<_>>()
+        };
+        let unlit = revisions(&mut app);
+        app.world_mut()
+            .run_system_once(move |mut voxels: VoxelWorld, blocks: Res<BlockRegistry>| {
+                for channel in LightChannel::ALL {
+                    LightGrid::new(&mut voxels, &blocks, channel).light_chunks(&[top, bottom]);
+                }
+            })
+            .unwrap();
+        // Lighting only invalidates meshes, so colliders built before it stay current.
+        for ((revision, light_revision), (lit_revision, lit_light_revision)) in
+            unlit.into_iter().zip(revisions(&mut app))
+        {
+            assert_eq!(lit_revision, revision);
+            assert!(lit_light_revision > light_revision);
+        }
+
+        let open = IVec3::new(20, floor_y, 20);
+        let shaded = IVec3::new(2, floor_y, 2);
+        assert_eq!(sky_light(&mut app, open), Some(MAX_LIGHT_LEVEL));
+        assert_eq!(sky_light(&mut app, shaded), Some(MAX_LIGHT_LEVEL - 6));
+
+        // Opening a hole in the roof lets daylight straight down the column.
+        let roof_y = top.y * CHUNK_VOXELS_SIZE as i32 + 4;
+        set_voxel(&mut app, IVec3::new(2, roof_y, 2), Voxel::default());
+        assert_eq!(sky_light(&mut app, shaded), Some(MAX_LIGHT_LEVEL));
+
+        // Closing an open column shades everything below it.
+        set_voxel(&mut app, IVec3::new(20, floor_y + 10, 20), stone);
+        assert_eq!(sky_light(&mut app, open), Some(MAX_LIGHT_LEVEL - 1));
+        assert_eq!(
+            sky_light(&mut app, IVec3::new(20, floor_y + 10, 20)),
+            Some(0)
+        );
+    }
+}
@@ -1,10 +1,25 @@
+use std::path::Path;
+
 use bevy::prelude::*;
 use bevy::window::{ExitCondition, WindowCloseRequested};
 
+mod biome;
+mod block;
+mod cave;
+mod decoration;
+mod fluid;
+mod generator;
+mod gravity;
+mod light;
+mod mapgen;
+mod ore;
+mod palette;
 mod physics;
 mod player;
 mod render;
+mod rng;
 mod save;
+mod spawn;
 mod terrain;
 mod ui;
 mod voxel;
@@ -13,13 +28,18 @@ mod world;
 #[cfg(debug_assertions)]
 mod debug_remote;
 
+use block::BlockPlugin;
 #[cfg(debug_assertions)]
 use debug_remote::DebugRemotePlugin;
+use fluid::FluidPlugin;
+use gravity::GravityPlugin;
+use light::LightPlugin;
 use physics::PhysicsPlugin;
 use player::PlayerPlugin;
 use render::RenderPlugin;
 use save::SavePlugin;
-use ui::UiPlugin;
+use terrain::TerrainSettings;
+use ui::{NewWorldTerrain, UiPlugin};
 use world::WorldPlugin;
 
 #[derive(States, Default, Debug, Clone, Eq, PartialEq, Hash)]
@@ -31,7 +51,31 @@ pub enum AppState {
     Paused,
 }
 
+const USAGE: &str = "\
+Usage: gecynd [--terrain-settings FILE]
+       gecynd mapgen ...
+
+--terrain-settings reads the terrain settings new worlds are created with, as JSON in the format
+saved in world metadata; missing fields keep their defaults. Existing worlds keep their own.";
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "mapgen") {
+        if let Err(error) = mapgen::run(&args[1..]) {
+            eprintln!("{error}\n\n{}", mapgen::USAGE);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let new_world_terrain = match parse_new_world_terrain(&args) {
+        Ok(terrain) => terrain,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            std::process::exit(1);
+        }
+    };
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -43,9 +87,14 @@ fn main() {
         }))
         .init_state::<AppState>()
         .insert_resource(ClearColor(Color::srgb(0.58, 0.76, 0.90)))
+        .insert_resource(NewWorldTerrain(new_world_terrain))
         .add_plugins((
+            BlockPlugin,
             SavePlugin,
             WorldPlugin,
+            FluidPlugin,
+            GravityPlugin,
+            LightPlugin,
             PlayerPlugin,
             PhysicsPlugin,
             RenderPlugin,
@@ -56,6 +105,14 @@ fn main() {
         .run();
 }
 
+fn parse_new_world_terrain(args: &[String]) -> Result<TerrainSettings, String> {
+    match args {
+        [] => Ok(TerrainSettings::default()),
+        [flag, path] if flag == "--terrain-settings" => TerrainSettings::load(Path::new(path)),
+        [flag, ..] => Err(format!("Unexpected argument {flag}")),
+    }
+}
+
 #[cfg(debug_assertions)]
 fn debug_plugins() -> DebugRemotePlugin {
     DebugRemotePlugin
@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use bevy::prelude::*;
+
+use crate::biome::Biome;
+use crate::block::BlockRegistry;
+use crate::generator::column_tops;
+use crate::terrain::{TerrainGenerator, TerrainSettings};
+use crate::voxel::{BlockId, VOXEL_SIZE};
+use crate::world::{CHUNK_VOXELS_SIZE, ChunkCoord};
+
+pub const USAGE: &str = "\
+Usage: gecynd mapgen --output FILE [--seed N] [--settings FILE] [--from X,Z] [--to X,Z]
+                     [--kind height|material|biome]
+
+Writes a top-down map of a noise world, one pixel per voxel column, without opening a window.
+--from and --to are inclusive chunk coordinates (default -8,-8 and 7,7); +X points right and
++Z points down. --settings reads terrain settings as JSON in the format saved in world
+metadata; missing fields keep their defaults. Height maps are written as grayscale PGM, material
+and biome maps as color PPM.";
+
+/// What a map shows for each column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapKind {
+    /// Height of the ground, from black at the lowest possible surface to white at the highest.
+    Height,
+    /// Color of the topmost block, including water and decorations, shaded by its height.
+    Material,
+    /// Color of the dominant biome's surface block.
+    Biome,
+}
+
+/// A map to render, parsed from the `mapgen` command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapRequest {
+    pub seed: u32,
+    pub settings: TerrainSettings,
+    pub min_chunk: IVec2,
+    pub max_chunk: IVec2,
+    pub kind: MapKind,
+    pub output: PathBuf,
+}
+
+impl MapRequest {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut seed = 0;
+        let mut settings = TerrainSettings::default();
+        let mut from = IVec2::splat(-8);
+        let mut to = IVec2::splat(7);
+        let mut kind = MapKind::Height;
+        let mut output = None;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for {flag}"))?;
+            match flag.as_str() {
+                "--seed" => {
+                    seed = value
+                        .parse()
+                        .map_err(|error| format!("Invalid seed {value}: {error}"))?;
+                }
+                "--settings" => settings = TerrainSettings::load(Path::new(value))?,
+                "--from" => from = parse_chunk_column(value)?,
+                "--to" => to = parse_chunk_column(value)?,
+                "--kind" => {
+                    kind = match value.as_str() {
+                        "height" => MapKind::Height,
+                        "material" => MapKind::Material,
+                        "biome" => MapKind::Biome,
+                        _ => return Err(format!("Unknown map kind {value}")),
+                    };
+                }
+                "--output" => output = Some(PathBuf::from(value)),
+                _ => return Err(format!("Unknown option {flag}")),
+            }
+        }
+
+        Ok(Self {
+            seed,
+            settings,
+            min_chunk: from.min(to),
+            max_chunk: from.max(to),
+            kind,
+            output: output.ok_or("Missing --output")?,
+        })
+    }
+
+    fn size_in_chunks(&self) -> IVec2 {
+        self.max_chunk - self.min_chunk + IVec2::ONE
+    }
+}
+
+fn parse_chunk_column(value: &str) -> Result<IVec2, String> {
+    let invalid = || format!("Invalid chunk coordinates {value}, expected X,Z");
+    let (x, z) = value.split_once(',').ok_or_else(invalid)?;
+    let x = x.trim().parse().map_err(|_| invalid())?;
+    let z = z.trim().parse().map_err(|_| invalid())?;
+    Ok(IVec2::new(x, z))
+}
+
+/// Entry point of `gecynd mapgen`: renders the requested map and writes it to disk.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let request = MapRequest::parse(args)?;
+    let registry = BlockRegistry::default();
+    let image = render_map(&request, &registry);
+    fs::write(&request.output, image)
+        .map_err(|error| format!("Failed to write {}: {error}", request.output.display()))?;
+
+    let size = request.size_in_chunks() * CHUNK_VOXELS_SIZE as i32;
+    println!(
+        "Wrote {}x{} map to {}",
+        size.x,
+        size.y,
+        request.output.display()
+    );
+    Ok(())
+}
+
+/// Renders the requested map as a binary PGM or PPM image. Chunk columns are spread over all
+/// available threads, sharing one generator like the game's chunk tasks do.
+pub fn render_map(request: &MapRequest, registry: &BlockRegistry) -> Vec<u8> {
+    let terrain = TerrainGenerator::new(request.seed, registry, request.settings);
+    let size = request.size_in_chunks();
+    let columns: Vec<IVec2> = (0..size.y)
+        .flat_map(|z| (0..size.x).map(move |x| request.min_chunk + IVec2::new(x, z)))
+        .collect();
+    let threads = thread::available_parallelism().map_or(1, |count| count.get());
+    let batch = columns.len().div_ceil(threads).max(1);
+
+    let tiles: Vec<Vec<[u8; 3]>> = thread::scope(|scope| {
+        let workers: Vec<_> = columns
+            .chunks(batch)
+            .map(|batch| {
+                let terrain = &terrain;
+                scope.spawn(move || {
+                    batch
+                        .iter()
+                        .map(|&column| render_tile(terrain, registry, request.kind, column))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("map worker panicked"))
+            .collect()
+    });
+
+    let tile_size = CHUNK_VOXELS_SIZE;
+    let width = size.x as usize * tile_size;
+    let height = size.y as usize * tile_size;
+    let channels = if request.kind == MapKind::Height {
+        1
+    } else {
+        3
+    };
+    let magic = if channels == 1 { "P5" } else { "P6" };
+    let mut image = format!("{magic}\n{width} {height}\n255\n").into_bytes();
+    let header = image.len();
+    image.resize(header + width * height * channels, 0);
+
+    for (tile_index, tile) in tiles.iter().enumerate() {
+        let tile_x = tile_index % size.x as usize;
+        let tile_z = tile_index / size.x as usize;
+        for (pixel_index, pixel) in tile.iter().enumerate() {
+            let x = tile_x * tile_size + pixel_index % tile_size;
+            let z = tile_z * tile_size + pixel_index / tile_size;
+            let offset = header + (x + z * width) * channels;
+            image[offset..offset + channels].copy_from_slice(&pixel[..channels]);
+        }
+    }
+    image
+}
+
+/// Pixels of one chunk column, indexed `x + z * CHUNK_VOXELS_SIZE`.
+fn render_tile(
+    terrain: &TerrainGenerator,
+    registry: &BlockRegistry,
+    kind: MapKind,
+    column: IVec2,
+) -> Vec<[u8; 3]> {
+    let origin = ChunkCoord::new(column.x, 0, column.y).origin_cell();
+    let size = CHUNK_VOXELS_SIZE as i32;
+    let settings = terrain.settings();
+    let min_surface_y = (settings.min_height_meters / VOXEL_SIZE).floor() as i32 - 1;
+    let max_surface_y = (settings.max_height_meters / VOXEL_SIZE).floor() as i32;
+    let brightness = |y: i32| {
+        ((y - min_surface_y) as f32 / (max_surface_y - min_surface_y) as f32).clamp(0.0, 1.0)
+    };
+
+    match kind {
+        MapKind::Height | MapKind::Biome => (0..size)
+            .flat_map(|z| (0..size).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let (surface_y, biome) = terrain.surface_column(origin.x + x, origin.z + z);
+                match kind {
+                    MapKind::Height => [(brightness(surface_y) * 255.0).round() as u8; 3],
+                    _ => biome_color(registry, biome),
+                }
+            })
+            .collect(),
+        MapKind::Material => column_tops(terrain, column.x, column.y)
+            .into_iter()
+            .map(|top| match top {
+                Some((y, block)) => block_color(registry, block, 0.55 + 0.45 * brightness(y)),
+                None => [0; 3],
+            })
+            .collect(),
+    }
+}
+
+fn block_color(registry: &BlockRegistry, block: BlockId, shade: f32) -> [u8; 3] {
+    let [red, green, blue, _] = registry
+        .get(block)
+        .map_or([0.0; 4], |definition| definition.color);
+    [red, green, blue].map(|channel| (channel * shade * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+fn biome_color(registry: &BlockRegistry, biome: Biome) -> [u8; 3] {
+    let surface = registry.generated_block(biome.terrain().surface);
+    block_color(registry, surface, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_have_one_pixel_per_column() {
+        let registry = BlockRegistry::default();
+        let args = [
+            "--seed", "7", "--from", "1,0", "--to", "0,0", "--output", "map.pgm",
+        ]
+        .map(String::from);
+        let mut request = MapRequest::parse(&args).unwrap();
+        assert_eq!(request.min_chunk, IVec2::new(0, 0));
+        assert_eq!(request.max_chunk, IVec2::new(1, 0));
+
+        let heights = render_map(&request, &registry);
+        let header = b"P5\n64 32\n255\n";
+        assert_eq!(&heights[..header.len()], header);
+        assert_eq!(heights.len(), header.len() + 64 * 32);
+        let terrain = TerrainGenerator::new(7, &registry, TerrainSettings::default());
+        let (x, z) = (0..64)
+            .flat_map(|x| (0..32).map(move |z| (x, z)))
+            .max_by_key(|&(x, z)| terrain.surface_column(x, z).0)
+            .unwrap();
+        let pixels = &heights[header.len()..];
+        assert_eq!(
+            pixels[x as usize + z as usize * 64],
+            *pixels.iter().max().unwrap()
+        );
+
+        request.kind = MapKind::Material;
+        let materials = render_map(&request, &registry);
+        let header = b"P6\n64 32\n255\n";
+        assert_eq!(&materials[..header.len()], header);
+        assert_eq!(materials.len(), header.len() + 64 * 32 * 3);
+        assert!(
+            materials[header.len()..]
+                .chunks(3)
+                .all(|pixel| pixel != [0; 3])
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::block::BlockRegistry;
+use crate::rng::{SeededRng, cell_key};
+use crate::voxel::{BlockId, VOXEL_SIZE, Voxel};
+use crate::world::{CHUNK_VOXELS_SIZE, Chunk, ChunkCoord, FACE_NEIGHBORS};
+
+/// Largest ore vein, which bounds how far a vein reaches from the chunk it starts in.
+const MAX_VEIN_SIZE: u32 = 24;
+
+/// Where and how often one ore generates. Ores replace stone in veins of connected voxels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OreVeinSettings {
+    /// Lowest world height, in meters, the ore appears at.
+    pub min_height_meters: f32,
+    /// Highest world height, in meters, the ore appears at.
+    pub max_height_meters: f32,
+    /// Average number of veins starting in each chunk inside the height range.
+    pub veins_per_chunk: f32,
+    /// Number of voxels in one vein, clamped to `MAX_VEIN_SIZE`.
+    pub vein_size: u32,
+}
+
+/// Vein settings of every ore. An ore left out of saved settings keeps its defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OreSettings {
+    pub coal: OreVeinSettings,
+    pub copper: OreVeinSettings,
+    pub iron: OreVeinSettings,
+    pub gold: OreVeinSettings,
+}
+
+impl OreSettings {
+    /// Block each ore places, in the order of `veins`.
+    pub const BLOCK_NAMES: [&str; 4] = ["coal_ore", "copper_ore", "iron_ore", "gold_ore"];
+
+    fn veins(&self) -> [OreVeinSettings; 4] {
+        [self.coal, self.copper, self.iron, self.gold]
+    }
+}
+
+impl Default for OreSettings {
+    fn default() -> Self {
+        let vein =
+            |min_height_meters, max_height_meters, veins_per_chunk, vein_size| OreVeinSettings {
+                min_height_meters,
+                max_height_meters,
+                veins_per_chunk,
+                vein_size,
+            };
+        Self {
+            coal: vein(0.0, 8.0, 6.0, 12),
+            copper: vein(-4.0, 6.0, 4.0, 10),
+            iron: vein(-8.0, 4.0, 3.0, 8),
+            gold: vein(-16.0, -2.0, 1.5, 6),
+        }
+    }
+}
+
+/// Ore block and generation settings in cells.
+struct Ore {
+    block: BlockId,
+    min_cell_y: i32,
+    max_cell_y: i32,
+    veins_per_chunk: f32,
+    vein_size: usize,
+}
+
+impl Ore {
+    fn new(block: BlockId, settings: OreVeinSettings) -> Self {
+        Self {
+            block,
+            min_cell_y: (settings.min_height_meters / VOXEL_SIZE).floor() as i32,
+            max_cell_y: (settings.max_height_meters / VOXEL_SIZE).ceil() as i32 - 1,
+            veins_per_chunk: settings.veins_per_chunk.max(0.0),
+            vein_size: settings.vein_size.clamp(1, MAX_VEIN_SIZE) as usize,
+        }
+    }
+}
+
+/// Grows ore veins into the stone of generated chunks. Every vein is seeded from the chunk it
+/// starts in, so a vein crossing a chunk border comes out the same on both sides.
+pub struct OreVeins {
+    seed: u32,
+    stone: BlockId,
+    ores: Vec<Ore>,
+}
+
+impl OreVeins {
+    pub fn new(seed: u32, registry: &BlockRegistry, settings: &OreSettings) -> Self {
+        Self {
+            seed,
+            stone: registry.generated_block("stone"),
+            ores: OreSettings::BLOCK_NAMES
+                .into_iter()
+                .zip(settings.veins())
+                .map(|(name, vein)| Ore::new(registry.generated_block(name), vein))
+                .collect(),
+        }
+    }
+
+    /// Replaces stone in the chunk with the parts of every vein that reach into it.
+    pub fn place_in_chunk(&self, chunk: &mut Chunk) {
+        let chunk_min = chunk.coord.origin_cell();
+        let chunk_max = chunk_min + IVec3::splat(CHUNK_VOXELS_SIZE as i32 - 1);
+        if !chunk.may_contain(|voxel| voxel.block == self.stone) {
+            return;
+        }
+
+        for (ore_index, ore) in self.ores.iter().enumerate() {
+            if ore.max_cell_y < chunk_min.y - MAX_VEIN_SIZE as i32
+                || ore.min_cell_y > chunk_max.y + MAX_VEIN_SIZE as i32
+            {
+                continue;
+            }
+
+            // Veins never reach past the neighboring chunks.
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let source = chunk.coord.offset(dx, dy, dz);
+                        for cell in self.source_veins(source, ore_index, ore) {
+                            if cell.cmplt(chunk_min).any() || cell.cmpgt(chunk_max).any() {
+                                continue;
+                            }
+
+                            let local = (cell - chunk_min).as_uvec3();
+                            let (x, y, z) = (local.x as usize, local.y as usize, local.z as usize);
+                            if chunk.get_voxel(x, y, z).map(|voxel| voxel.block) == Some(self.stone)
+                            {
+                                chunk.set_voxel(x, y, z, Voxel::new(ore.block));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Cells of every vein of one ore that starts in the `source` chunk.
+    fn source_veins(&self, source: ChunkCoord, ore_index: usize, ore: &Ore) -> Vec<IVec3> {
+        let source_min = source.origin_cell();
+        let size = CHUNK_VOXELS_SIZE as i32;
+        let min_y = ore.min_cell_y.max(source_min.y);
+        let max_y = ore.max_cell_y.min(source_min.y + size - 1);
+        if min_y > max_y {
+            return Vec::new();
+        }
+
+        let key = cell_key(source_min) ^ ore_index as u64;
+        let mut rng = SeededRng::new(self.seed, key);
+        let whole_veins = ore.veins_per_chunk.floor();
+        let vein_count =
+            whole_veins as usize + usize::from(rng.unit() < ore.veins_per_chunk - whole_veins);
+
+        let mut cells = Vec::new();
+        for _ in 0..vein_count {
+            let start = IVec3::new(
+                source_min.x + rng.range(0, size),
+                rng.range(min_y, max_y + 1),
+                source_min.z + rng.range(0, size),
+            );
+            grow_vein(&mut rng, start, ore, &mut cells);
+        }
+        cells
+    }
+}
+
+/// Grows a clump of `ore.vein_size` connected cells from `start` by repeatedly adding a random
+/// face neighbor of a random cell already in the vein, staying inside the ore's height range.
+fn grow_vein(rng: &mut SeededRng, start: IVec3, ore: &Ore, cells: &mut Vec<IVec3>) {
+    let first = cells.len();
+    cells.push(start);
+
+    let mut attempts = ore.vein_size * 8;
+    while cells.len() - first < ore.vein_size && attempts > 0 {
+        attempts -= 1;
+        let base = cells[first + rng.range(0, (cells.len() - first) as i32) as usize];
+        let candidate = base + FACE_NEIGHBORS[rng.range(0, FACE_NEIGHBORS.len() as i32) as usize];
+        if (ore.min_cell_y..=ore.max_cell_y).contains(&candidate.y)
+            && !cells[first..].contains(&candidate)
+        {
+            cells.push(candidate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ore_density_follows_configured_depth_ranges() {
+        let registry = BlockRegistry::default();
+        let stone = registry.id_by_name("stone").unwrap();
+        let veins = OreVeins::new(5, &registry, &OreSettings::default());
+        let chunk_volume = CHUNK_VOXELS_SIZE.pow(3) as f32;
+
+        let mut counts = vec![vec![0usize; veins.ores.len()]; 16];
+        for chunk_y in -10..6 {
+            for chunk_x in 0..4 {
+                for chunk_z in 0..4 {
+                    let mut chunk = Chunk::new(ChunkCoord::new(chunk_x, chunk_y, chunk_z));
+                    for index in 0..CHUNK_VOXELS_SIZE.pow(3) {
+                        chunk.set_voxel_by_index(index, Voxel::new(stone));
+                    }
+                    veins.place_in_chunk(&mut chunk);
+
+                    for voxel in chunk.voxels() {
+                        if let Some(index) =
+                            veins.ores.iter().position(|ore| ore.block == voxel.block)
+                        {
+                            counts[(chunk_y + 10) as usize][index] += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        for (index, ore) in veins.ores.iter().enumerate() {
+            let expected = ore.veins_per_chunk * ore.vein_size as f32;
+            let (mut inside, mut inside_chunks) = (0, 0);
+            for chunk_y in -10..6 {
+                let min = chunk_y * CHUNK_VOXELS_SIZE as i32;
+                let max = min + CHUNK_VOXELS_SIZE as i32 - 1;
+                let count = counts[(chunk_y + 10) as usize][index];
+                if max < ore.min_cell_y || min > ore.max_cell_y {
+                    assert_eq!(
+                        count, 0,
+                        "ore {index} outside its range at chunk y {chunk_y}"
+                    );
+                } else if min >= ore.min_cell_y + CHUNK_VOXELS_SIZE as i32
+                    && max <= ore.max_cell_y - CHUNK_VOXELS_SIZE as i32
+                {
+                    inside += count;
+                    inside_chunks += 16;
+                }
+            }
+
+            assert!(inside_chunks > 0);
+            let density = inside as f32 / inside_chunks as f32;
+            assert!(
+                density > expected * 0.7 && density < expected * 1.1,
+                "ore {index}: {density} voxels per chunk, expected about {expected}"
+            );
+            assert!(density / chunk_volume < 0.01);
+        }
+    }
+}
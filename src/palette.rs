@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use crate::voxel::Voxel;
+
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+/// Palette-compressed voxel storage.
+///
+/// Each distinct voxel in the container is stored once in `palette`, and every cell holds a
+/// bit-packed index into it. A container holding a single value keeps no index words at all.
+/// The packed words are shared between clones until one of them is written to, so cloning a
+/// chunk for an async mesh or collider build does not copy voxel data.
+#[derive(Clone, Debug)]
+pub struct PalettedVoxels {
+    len: usize,
+    palette: Vec<Voxel>,
+    bits_per_index: u32,
+    words: Arc<Vec<u64>>,
+}
+
+impl PalettedVoxels {
+    pub fn new(len: usize, value: Voxel) -> Self {
+        Self {
+            len,
+            palette: vec![value],
+            bits_per_index: 0,
+            words: Arc::new(Vec::new()),
+        }
+    }
+
+    pub fn is_uniform(&self) -> bool {
+        self.bits_per_index == 0
+    }
+
+    pub fn get(&self, index: usize) -> Voxel {
+        if self.bits_per_index == 0 {
+            return self.palette[0];
+        }
+        self.palette[self.read_index(index)]
+    }
+
+    /// Writes a voxel and returns whether the stored value changed.
+    pub fn set(&mut self, index: usize, voxel: Voxel) -> bool {
+        if index >= self.len {
+            return false;
+        }
+
+        let palette_index = match self.palette.iter().position(|entry| *entry == voxel) {
+            Some(palette_index) => palette_index,
+            None => {
+                self.palette.push(voxel);
+                let required_bits = bits_for_palette(self.palette.len());
+                if required_bits > self.bits_per_index {
+                    self.repack(required_bits);
+                }
+                self.palette.len() - 1
+            }
+        };
+
+        if self.bits_per_index == 0 || self.read_index(index) == palette_index {
+            return false;
+        }
+
+        self.write_index(index, palette_index);
+        true
+    }
+
+    /// Returns true if any palette entry matches. Entries can outlive the cells that used them
+    /// until the next `compact`, so a match does not guarantee the value is still present.
+    pub fn palette_contains(&self, predicate: impl Fn(Voxel) -> bool) -> bool {
+        self.palette.iter().any(|voxel| predicate(*voxel))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Voxel> + '_ {
+        (0..self.len).map(|index| self.get(index))
+    }
+
+    /// Drops palette entries that are no longer referenced and shrinks the index width to match.
+    pub fn compact(&mut self) {
+        if self.bits_per_index == 0 {
+            return;
+        }
+
+        let mut used = vec![false; self.palette.len()];
+        for index in 0..self.len {
+            used[self.read_index(index)] = true;
+        }
+
+        if used.iter().all(|used| *used) {
+            return;
+        }
+
+        let mut remap = vec![0; self.palette.len()];
+        let mut palette = Vec::new();
+        for (old_index, voxel) in self.palette.iter().enumerate() {
+            if used[old_index] {
+                remap[old_index] = palette.len();
+                palette.push(*voxel);
+            }
+        }
+
+        let bits_per_index = bits_for_palette(palette.len());
+        let mut compacted = Self {
+            len: self.len,
+            palette,
+            bits_per_index,
+            words: Arc::new(vec![0; word_count(self.len, bits_per_index)]),
+        };
+        if bits_per_index > 0 {
+            for index in 0..self.len {
+                compacted.write_index(index, remap[self.read_index(index)]);
+            }
+        }
+
+        *self = compacted;
+    }
+
+    /// Heap bytes used by the palette and packed indices.
+    pub fn heap_size(&self) -> usize {
+        self.palette.len() * std::mem::size_of::<Voxel>()
+            + self.words.len() * std::mem::size_of::<u64>()
+    }
+
+    fn repack(&mut self, bits_per_index: u32) {
+        let mut words = vec![0; word_count(self.len, bits_per_index)];
+        for index in 0..self.len {
+            let palette_index = if self.bits_per_index == 0 {
+                0
+            } else {
+                self.read_index(index)
+            };
+            write_packed(&mut words, bits_per_index, index, palette_index);
+        }
+        self.bits_per_index = bits_per_index;
+        self.words = Arc::new(words);
+    }
+
+    fn read_index(&self, index: usize) -> usize {
+        let bits = self.bits_per_index as usize;
+        let per_word = BITS_PER_WORD / bits;
+        let word = self.words[index / per_word];
+        let shift = (index % per_word) * bits;
+        ((word >> shift) & ((1u64 << bits) - 1)) as usize
+    }
+
+    fn write_index(&mut self, index: usize, palette_index: usize) {
+        let bits_per_index = self.bits_per_index;
+        write_packed(
+            Arc::make_mut(&mut self.words).as_mut_slice(),
+            bits_per_index,
+            index,
+            palette_index,
+        );
+    }
+}
+
+fn bits_for_palette(palette_len: usize) -> u32 {
+    if palette_len <= 1 {
+        0
+    } else {
+        usize::BITS - (palette_len - 1).leading_zeros()
+    }
+}
+
+fn word_count(len: usize, bits_per_index: u32) -> usize {
+    if bits_per_index == 0 {
+        0
+    } else {
+        len.div_ceil(BITS_PER_WORD / bits_per_index as usize)
+    }
+}
+
+fn write_packed(words: &mut [u64], bits_per_index: u32, index: usize, palette_index: usize) {
+    let bits = bits_per_index as usize;
+    let per_word = BITS_PER_WORD / bits;
+    let shift = (index % per_word) * bits;
+    let mask = ((1u64 << bits) - 1) << shift;
+    let word = &mut words[index / per_word];
+    *word = (*word & !mask) | (((palette_index as u64) << shift) & mask);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::BlockId;
+
+    #[test]
+    fn grows_and_compacts_palette() {
+        let stone = Voxel::new(BlockId(1));
+        let dirt = Voxel::new(BlockId(2));
+        let mut voxels = PalettedVoxels::new(4096, Voxel::default());
+        assert!(voxels.is_uniform());
+        assert_eq!(voxels.heap_size(), std::mem::size_of::<Voxel>());
+
+        for index in 0..4096 {
+            let voxel = if index % 3 == 0 { stone } else { dirt };
+            assert!(voxels.set(index, voxel));
+        }
+        assert!(!voxels.set(0, stone));
+        assert_eq!(voxels.palette.len(), 3);
+        assert!(
+            (0..4096).all(|index| voxels.get(index) == if index % 3 == 0 { stone } else { dirt })
+        );
+
+        voxels.compact();
+        assert_eq!(voxels.palette.len(), 2);
+        assert!(
+            (0..4096).all(|index| voxels.get(index) == if index % 3 == 0 { stone } else { dirt })
+        );
+    }
+
+    #[test]
+    fn clones_share_storage_until_written() {
+        let mut original = PalettedVoxels::new(64, Voxel::default());
+        original.set(5, Voxel::new(BlockId(1)));
+
+        let mut copy = original.clone();
+        assert!(Arc::ptr_eq(&original.words, &copy.words));
+
+        copy.set(6, Voxel::new(BlockId(1)));
+        assert!(!Arc::ptr_eq(&original.words, &copy.words));
+        assert_eq!(original.get(6), Voxel::default());
+        assert_eq!(copy.get(6), Voxel::new(BlockId(1)));
+    }
+}
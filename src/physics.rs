@@ -1,6 +1,7 @@
 use crate::AppState;
+use crate::block::BlockRegistry;
 use crate::voxel::{VOXEL_SIZE, VoxelFace};
-use crate::world::{CHUNK_VOXELS_HEIGHT, CHUNK_VOXELS_SIZE, Chunk, ChunkCoord, World};
+use crate::world::{CHUNK_VOXELS_SIZE, Chunk, ChunkCoord, ViewDistanceSettings, World};
 use bevy::prelude::*;
 use bevy::tasks::{AsyncComputeTaskPool, Task, futures_lite::future};
 use bevy_rapier3d::prelude::*;
@@ -11,13 +12,16 @@ pub(crate) struct PendingPhysicsCollider(Task<(u64, Option<Collider>)>);
 #[derive(Clone)]
 struct ChunkPhysicsInput {
     chunk: Chunk,
-    neighbors: HorizontalChunkNeighbors,
+    blocks: BlockRegistry,
+    neighbors: ChunkNeighbors,
 }
 
 #[derive(Clone, Default)]
-struct HorizontalChunkNeighbors {
+struct ChunkNeighbors {
     negative_x: Option<Chunk>,
     positive_x: Option<Chunk>,
+    negative_y: Option<Chunk>,
+    positive_y: Option<Chunk>,
     negative_z: Option<Chunk>,
     positive_z: Option<Chunk>,
 }
@@ -29,7 +33,8 @@ impl Plugin for PhysicsPlugin {
         app.add_plugins((RapierPhysicsPlugin::<NoUserData>::default(),))
             .add_systems(
                 Update,
-                queue_chunk_physics_builds
+                (release_distant_chunk_physics, queue_chunk_physics_builds)
+                    .chain()
                     .run_if(in_state(AppState::LoadingWorld).or(in_state(AppState::InGame))),
             )
             .add_systems(
@@ -43,6 +48,10 @@ impl Plugin for PhysicsPlugin {
 #[derive(Component)]
 pub struct ChunkPhysics;
 
+#[allow(
+    clippy::type_complexity,
+    reason = "the query filter pairs the refresh marker with the in-flight build marker"
+)]
 fn queue_chunk_physics_builds(
     mut commands: Commands,
     world: Res<World>,
@@ -54,13 +63,20 @@ fn queue_chunk_physics_builds(
         ),
     >,
     all_chunks: Query<&Chunk>,
+    blocks: Res<BlockRegistry>,
+    view: Res<ViewDistanceSettings>,
 ) {
     let task_pool = AsyncComputeTaskPool::get();
 
     for (entity, chunk) in chunk_query.iter() {
+        if !world.within_distance(chunk.coord, view.simulation_distance_chunks) {
+            continue;
+        }
+
         let input = ChunkPhysicsInput {
             chunk: chunk.clone(),
-            neighbors: gather_horizontal_neighbors(chunk.coord, &world, &all_chunks),
+            blocks: blocks.clone(),
+            neighbors: gather_chunk_neighbors(chunk.coord, &world, &all_chunks),
         };
         let revision = input.chunk.revision;
         let task = task_pool.spawn(async move { (revision, generate_chunk_collider(&input)) });
@@ -68,6 +84,24 @@ fn queue_chunk_physics_builds(
     }
 }
 
+/// Drops the colliders of chunks that left the simulation distance, flagging them to be rebuilt
+/// once they are back in range.
+fn release_distant_chunk_physics(
+    mut commands: Commands,
+    world: Res<World>,
+    view: Res<ViewDistanceSettings>,
+    chunk_query: Query<(Entity, &Chunk), With<ChunkPhysics>>,
+) {
+    for (entity, chunk) in chunk_query.iter() {
+        if !world.within_distance(chunk.coord, view.simulation_distance_chunks) {
+            commands
+                .entity(entity)
+                .remove::<(ChunkPhysics, RigidBody, Collider)>()
+                .insert(crate::player::NeedsPhysicsRefresh);
+        }
+    }
+}
+
 fn process_chunk_physics_builds(
     mut commands: Commands,
     mut chunk_query: Query<(Entity, &mut PendingPhysicsCollider, &Chunk)>,
@@ -103,28 +137,32 @@ fn process_chunk_physics_builds(
 }
 
 fn generate_chunk_collider(input: &ChunkPhysicsInput) -> Option<Collider> {
+    if input.chunk.is_empty() {
+        return None;
+    }
+
     let mut vertices = Vec::new();
     let mut indices = Vec::new();
 
     for x in 0..CHUNK_VOXELS_SIZE {
-        for y in 0..CHUNK_VOXELS_HEIGHT {
+        for y in 0..CHUNK_VOXELS_SIZE {
             for z in 0..CHUNK_VOXELS_SIZE {
-                if let Some(voxel) = input.chunk.get_voxel(x, y, z) {
-                    if voxel.is_solid() {
-                        let local_x = x as f32 * VOXEL_SIZE;
-                        let local_y = y as f32 * VOXEL_SIZE;
-                        let local_z = z as f32 * VOXEL_SIZE;
-
-                        add_voxel_geometry(
-                            &mut vertices,
-                            &mut indices,
-                            Vec3::new(local_x, local_y, local_z),
-                            input,
-                            x,
-                            y,
-                            z,
-                        );
-                    }
+                if let Some(voxel) = input.chunk.get_voxel(x, y, z)
+                    && input.blocks.is_solid(voxel.block)
+                {
+                    let local_x = x as f32 * VOXEL_SIZE;
+                    let local_y = y as f32 * VOXEL_SIZE;
+                    let local_z = z as f32 * VOXEL_SIZE;
+
+                    add_voxel_geometry(
+                        &mut vertices,
+                        &mut indices,
+                        Vec3::new(local_x, local_y, local_z),
+                        input,
+                        x,
+                        y,
+                        z,
+                    );
                 }
             }
         }
@@ -180,6 +218,8 @@ fn add_voxel_geometry(
     }
 }
 
+/// Colliders only care about solidity, so faces between two solid voxels are culled even when
+/// one of them is translucent.
 fn should_render_face_physics(
     input: &ChunkPhysicsInput,
     x: usize,
@@ -193,7 +233,8 @@ fn should_render_face_physics(
     let ny = y as i32 + dy;
     let nz = z as i32 + dz;
 
-    neighbor_voxel_for_face(input, nx, ny, nz).is_none_or(|voxel| !voxel.is_solid())
+    neighbor_voxel_for_face(input, nx, ny, nz)
+        .is_none_or(|voxel| !input.blocks.is_solid(voxel.block))
 }
 
 fn neighbor_voxel_for_face(
@@ -202,66 +243,44 @@ fn neighbor_voxel_for_face(
     ny: i32,
     nz: i32,
 ) -> Option<crate::voxel::Voxel> {
-    if ny < 0 || ny >= CHUNK_VOXELS_HEIGHT as i32 {
-        return None;
-    }
-
-    if (0..CHUNK_VOXELS_SIZE as i32).contains(&nx) && (0..CHUNK_VOXELS_SIZE as i32).contains(&nz) {
-        return input
-            .chunk
-            .get_voxel(nx as usize, ny as usize, nz as usize)
-            .copied();
-    }
-
-    if nx < 0 {
-        return input
-            .neighbors
-            .negative_x
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(CHUNK_VOXELS_SIZE - 1, ny as usize, nz as usize))
-            .copied();
-    }
-
-    if nx >= CHUNK_VOXELS_SIZE as i32 {
-        return input
-            .neighbors
-            .positive_x
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(0, ny as usize, nz as usize))
-            .copied();
-    }
-
-    if nz < 0 {
-        return input
-            .neighbors
-            .negative_z
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(nx as usize, ny as usize, CHUNK_VOXELS_SIZE - 1))
-            .copied();
-    }
-
-    if nz >= CHUNK_VOXELS_SIZE as i32 {
-        return input
-            .neighbors
-            .positive_z
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(nx as usize, ny as usize, 0))
-            .copied();
-    }
-
-    None
+    let size = CHUNK_VOXELS_SIZE as i32;
+    let neighbor = if nx < 0 {
+        &input.neighbors.negative_x
+    } else if nx >= size {
+        &input.neighbors.positive_x
+    } else if ny < 0 {
+        &input.neighbors.negative_y
+    } else if ny >= size {
+        &input.neighbors.positive_y
+    } else if nz < 0 {
+        &input.neighbors.negative_z
+    } else if nz >= size {
+        &input.neighbors.positive_z
+    } else {
+        return input.chunk.get_voxel(nx as usize, ny as usize, nz as usize);
+    };
+
+    neighbor.as_ref().and_then(|chunk| {
+        chunk.get_voxel(
+            nx.rem_euclid(size) as usize,
+            ny.rem_euclid(size) as usize,
+            nz.rem_euclid(size) as usize,
+        )
+    })
 }
 
-fn gather_horizontal_neighbors(
+fn gather_chunk_neighbors(
     coord: ChunkCoord,
     world: &World,
     chunk_query: &Query<&Chunk>,
-) -> HorizontalChunkNeighbors {
-    HorizontalChunkNeighbors {
-        negative_x: get_chunk_clone(ChunkCoord::new(coord.x - 1, coord.z), world, chunk_query),
-        positive_x: get_chunk_clone(ChunkCoord::new(coord.x + 1, coord.z), world, chunk_query),
-        negative_z: get_chunk_clone(ChunkCoord::new(coord.x, coord.z - 1), world, chunk_query),
-        positive_z: get_chunk_clone(ChunkCoord::new(coord.x, coord.z + 1), world, chunk_query),
+) -> ChunkNeighbors {
+    ChunkNeighbors {
+        negative_x: get_chunk_clone(coord.offset(-1, 0, 0), world, chunk_query),
+        positive_x: get_chunk_clone(coord.offset(1, 0, 0), world, chunk_query),
+        negative_y: get_chunk_clone(coord.offset(0, -1, 0), world, chunk_query),
+        positive_y: get_chunk_clone(coord.offset(0, 1, 0), world, chunk_query),
+        negative_z: get_chunk_clone(coord.offset(0, 0, -1), world, chunk_query),
+        positive_z: get_chunk_clone(coord.offset(0, 0, 1), world, chunk_query),
     }
 }
 
@@ -290,3 +309,85 @@ fn add_face_geometry(
     indices.push([start_vertex, start_vertex + 1, start_vertex + 2]);
     indices.push([start_vertex, start_vertex + 2, start_vertex + 3]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::WorldPreset;
+    use crate::player::{Inventory, NeedsPhysicsRefresh, Player};
+    use crate::save::SaveState;
+    use crate::terrain::TerrainSettings;
+    use crate::world::{WorldPlugin, chunk_world_origin};
+    use bevy::state::app::StatesPlugin;
+
+    #[test]
+    fn chunks_past_the_render_distance_get_colliders_at_their_origin() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(StatesPlugin)
+            .init_state::<AppState>()
+            .init_resource::<SaveState>()
+            .init_resource::<Inventory>()
+            .init_resource::<BlockRegistry>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(ViewDistanceSettings {
+                render_distance_chunks: 1,
+                simulation_distance_chunks: 2,
+            })
+            .add_plugins(WorldPlugin)
+            .add_systems(
+                Update,
+                (queue_chunk_physics_builds, process_chunk_physics_builds)
+                    .run_if(in_state(AppState::InGame)),
+            );
+
+        let blocks = app.world().resource::<BlockRegistry>().clone();
+        app.world_mut().resource_mut::<World>().generator =
+            Some(WorldPreset::Superflat.generator(0, TerrainSettings::default(), &blocks));
+        app.world_mut().spawn((Player, Transform::default()));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+
+        loop {
+            app.update();
+            let world = app.world().resource::<World>();
+            let generating = !world.pending_chunks.is_empty() || !world.queued_chunks.is_empty();
+            let mut building = app
+                .world_mut()
+                .query_filtered::<(), Or<(With<PendingPhysicsCollider>, With<NeedsPhysicsRefresh>)>>();
+            if !generating && building.iter(app.world()).next().is_none() {
+                break;
+            }
+
+            let world = app.world().resource::<World>();
+            while !world.pending_chunks.values().all(Task::is_finished) {
+                std::thread::yield_now();
+            }
+            let mut pending = app.world_mut().query::<&PendingPhysicsCollider>();
+            while !pending
+                .iter(app.world())
+                .all(|pending| pending.0.is_finished())
+            {
+                std::thread::yield_now();
+            }
+        }
+
+        let mut colliders = app
+            .world_mut()
+            .query_filtered::<(&Chunk, &Transform), With<Collider>>();
+        let colliders = colliders
+            .iter(app.world())
+            .map(|(chunk, transform)| (chunk.coord, transform.translation))
+            .collect::<Vec<_>>();
+        let focus = ChunkCoord::new(0, 0, 0);
+        assert!(
+            colliders
+                .iter()
+                .any(|(coord, _)| coord.distance_squared(focus) > 1)
+        );
+        for (coord, translation) in colliders {
+            assert_eq!(translation, chunk_world_origin(coord), "chunk {coord:?}");
+        }
+    }
+}
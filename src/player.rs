@@ -11,8 +11,11 @@ use bevy::{
 use bevy_rapier3d::prelude::*;
 
 use crate::AppState;
-use crate::voxel::{VOXEL_SIZE, VoxelFace, VoxelType};
-use crate::world::{InitialWorldGeneration, World};
+use crate::block::BlockRegistry;
+use crate::voxel::{BlockId, VOXEL_SIZE, Voxel, VoxelFace};
+use crate::world::{
+    InitialWorldGeneration, VoxelAccess, VoxelReader, voxel_cell, voxel_cell_origin,
+};
 
 const PLAYER_WALK_SPEED: f32 = 4.5;
 const PLAYER_SPRINT_MULTIPLIER: f32 = 1.8;
@@ -20,8 +23,24 @@ const PLAYER_GRAVITY: f32 = 25.0;
 const PLAYER_MAX_FALL_SPEED: f32 = 40.0;
 const PLAYER_JUMP_SPEED: f32 = 6.5;
 const PLAYER_STEP_HEIGHT: f32 = 0.5;
+/// Half size of the player's collider, which is centered on the player's translation.
+pub const PLAYER_HALF_EXTENTS: Vec3 = Vec3::new(0.25, 1.0, 0.25);
+const PLAYER_FLUID_PROBE_DEPTH: f32 = 0.9;
 const FOG_COLOR: Color = Color::srgb(0.58, 0.76, 0.90);
 const FOG_DENSITY: f32 = 0.008;
+const STARTING_STACK_SIZE: u32 = 32;
+const HOTBAR_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
 
 #[derive(Component)]
 pub struct Player;
@@ -48,38 +67,38 @@ pub enum EditMode {
 
 #[derive(Clone, Debug)]
 pub struct EditOperation {
-    pub position: Vec3,
-    pub voxel_type: VoxelType,
+    pub cell: IVec3,
+    pub voxel: Voxel,
     pub mode: EditMode,
 }
 
 #[derive(Clone, Debug, Message)]
 pub struct EditRequest {
-    pub positions: Vec<Vec3>,
+    pub cells: Vec<IVec3>,
     pub operations: Vec<EditOperation>,
 }
 
 #[derive(Clone)]
 pub struct AppliedEditOperation {
-    pub position: Vec3,
-    pub after: VoxelType,
+    pub cell: IVec3,
+    pub after: Voxel,
 }
 
 #[derive(Resource)]
 pub struct PlayerInteraction {
-    pub selected_voxel_world_pos: Option<Vec3>,
+    pub selected_cell: Option<IVec3>,
     pub hit_face: Option<VoxelFace>,
     pub interaction_range: f32,
-    pub selected_material: VoxelType,
+    pub selected_material: BlockId,
 }
 
 impl Default for PlayerInteraction {
     fn default() -> Self {
         Self {
-            selected_voxel_world_pos: None,
+            selected_cell: None,
             hit_face: None,
             interaction_range: 10.0,
-            selected_material: VoxelType::Stone,
+            selected_material: BlockId::AIR,
         }
     }
 }
@@ -102,52 +121,54 @@ impl Default for PlacementCooldown {
     }
 }
 
-#[derive(Resource, Clone)]
+#[derive(Resource, Clone, Default)]
 pub struct Inventory {
-    items: HashMap<VoxelType, u32>,
+    items: HashMap<BlockId, u32>,
 }
 
-impl Default for Inventory {
-    fn default() -> Self {
-        let mut items = HashMap::new();
-        items.insert(VoxelType::Grass, 32);
-        items.insert(VoxelType::Dirt, 32);
-        items.insert(VoxelType::Stone, 32);
-        Self { items }
+impl Inventory {
+    /// Inventory for a fresh world: a stack of every hotbar block.
+    pub fn starting(blocks: &BlockRegistry) -> Self {
+        let mut inventory = Self::default();
+        for block in blocks.hotbar_blocks() {
+            inventory.add(*block, STARTING_STACK_SIZE);
+        }
+        inventory
     }
-}
 
-impl Inventory {
-    pub fn add(&mut self, voxel_type: VoxelType, count: u32) {
-        if voxel_type == VoxelType::Air || count == 0 {
+    pub fn add(&mut self, block: BlockId, count: u32) {
+        if block.is_air() || count == 0 {
             return;
         }
-        *self.items.entry(voxel_type).or_insert(0) += count;
+        *self.items.entry(block).or_insert(0) += count;
     }
 
-    pub fn count(&self, voxel_type: VoxelType) -> u32 {
-        self.items.get(&voxel_type).copied().unwrap_or(0)
+    pub fn count(&self, block: BlockId) -> u32 {
+        self.items.get(&block).copied().unwrap_or(0)
     }
 
-    pub fn try_remove(&mut self, voxel_type: VoxelType, count: u32) -> bool {
-        if voxel_type == VoxelType::Air || count == 0 {
+    pub fn try_remove(&mut self, block: BlockId, count: u32) -> bool {
+        if block.is_air() || count == 0 {
             return true;
         }
 
-        let current = self.count(voxel_type);
+        let current = self.count(block);
         if current < count {
             return false;
         }
 
-        self.items.insert(voxel_type, current - count);
+        self.items.insert(block, current - count);
         true
     }
 
-    pub fn entries(&self) -> Vec<(VoxelType, u32)> {
-        [VoxelType::Grass, VoxelType::Dirt, VoxelType::Stone]
-            .into_iter()
-            .map(|voxel_type| (voxel_type, self.count(voxel_type)))
-            .collect()
+    pub fn entries(&self) -> Vec<(BlockId, u32)> {
+        let mut entries = self
+            .items
+            .iter()
+            .map(|(block, count)| (*block, *count))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(block, _)| *block);
+        entries
     }
 
     pub fn clear(&mut self) {
@@ -155,20 +176,23 @@ impl Inventory {
     }
 }
 
-pub const HOTBAR_MATERIALS: [VoxelType; 3] = [VoxelType::Grass, VoxelType::Dirt, VoxelType::Stone];
-
-pub fn selected_material_index(selected_material: VoxelType) -> usize {
-    HOTBAR_MATERIALS
+pub fn selected_material_index(hotbar: &[BlockId], selected_material: BlockId) -> usize {
+    hotbar
         .iter()
         .position(|material| *material == selected_material)
-        .unwrap_or(HOTBAR_MATERIALS.len() - 1)
+        .unwrap_or(hotbar.len().saturating_sub(1))
 }
 
-fn set_selected_material_by_index(interaction: &mut PlayerInteraction, index: usize) {
-    interaction.selected_material = HOTBAR_MATERIALS
+fn set_selected_material_by_index(
+    interaction: &mut PlayerInteraction,
+    hotbar: &[BlockId],
+    index: usize,
+) {
+    interaction.selected_material = hotbar
         .get(index)
+        .or(hotbar.last())
         .copied()
-        .unwrap_or(VoxelType::Stone);
+        .unwrap_or(BlockId::AIR);
 }
 
 pub struct PlayerPlugin;
@@ -224,7 +248,11 @@ pub fn spawn_player(commands: &mut Commands, spawn_translation: Vec3) {
             Player,
             PlayerMotor::default(),
             RigidBody::KinematicPositionBased,
-            Collider::cuboid(0.25, 1.0, 0.25),
+            Collider::cuboid(
+                PLAYER_HALF_EXTENTS.x,
+                PLAYER_HALF_EXTENTS.y,
+                PLAYER_HALF_EXTENTS.z,
+            ),
             KinematicCharacterController {
                 translation: Some(Vec3::ZERO),
                 autostep: Some(CharacterAutostep {
@@ -273,11 +301,14 @@ fn player_movement(
         With<Player>,
     >,
     time: Res<Time<Fixed>>,
+    voxels: VoxelReader,
+    blocks: Res<BlockRegistry>,
 ) {
     if let Ok((mut controller, transform, controller_output, mut motor)) = player_query.single_mut()
     {
         let mut movement = Vec3::ZERO;
-        let mut speed = PLAYER_WALK_SPEED;
+        let fluid_multiplier = fluid_movement_multiplier(transform.translation, &voxels, &blocks);
+        let mut speed = PLAYER_WALK_SPEED * fluid_multiplier.unwrap_or(1.0);
 
         if keyboard_input.pressed(KeyCode::ShiftLeft) {
             speed *= PLAYER_SPRINT_MULTIPLIER;
@@ -304,7 +335,18 @@ fn player_movement(
             motor.vertical_velocity = 0.0;
         }
 
-        if keyboard_input.just_pressed(KeyCode::Space) && grounded {
+        if let Some(multiplier) = fluid_multiplier {
+            // Fluids damp gravity, and holding jump swims upward.
+            if keyboard_input.pressed(KeyCode::Space) {
+                motor.vertical_velocity = PLAYER_JUMP_SPEED * multiplier;
+            } else {
+                motor.vertical_velocity -= PLAYER_GRAVITY * multiplier * time.delta_secs();
+                motor.vertical_velocity = motor.vertical_velocity.clamp(
+                    -PLAYER_MAX_FALL_SPEED * multiplier,
+                    PLAYER_JUMP_SPEED * multiplier,
+                );
+            }
+        } else if keyboard_input.just_pressed(KeyCode::Space) && grounded {
             motor.vertical_velocity = PLAYER_JUMP_SPEED;
         } else {
             motor.vertical_velocity -= PLAYER_GRAVITY * time.delta_secs();
@@ -330,17 +372,31 @@ fn player_movement(
     }
 }
 
+/// Returns the strongest movement damping of any fluid at the player's feet or body.
+fn fluid_movement_multiplier(
+    player_pos: Vec3,
+    voxels: &VoxelReader,
+    blocks: &BlockRegistry,
+) -> Option<f32> {
+    [player_pos - Vec3::Y * PLAYER_FLUID_PROBE_DEPTH, player_pos]
+        .into_iter()
+        .filter_map(|probe| voxels.get(voxel_cell(probe)))
+        .filter_map(|voxel| blocks.fluid(voxel.block))
+        .map(|fluid| fluid.movement_multiplier)
+        .reduce(f32::min)
+}
+
 fn player_look(
     mut mouse_motion: MessageReader<MouseMotion>,
     mut player_query: Query<&mut Transform, With<Player>>,
     mut camera_query: Query<&mut Transform, (With<PlayerCamera>, Without<Player>)>,
     cursor_query: Query<&CursorOptions, With<PrimaryWindow>>,
 ) {
-    if let Ok(cursor_options) = cursor_query.single() {
-        if cursor_options.grab_mode != CursorGrabMode::Locked {
-            for _ in mouse_motion.read() {}
-            return;
-        }
+    if let Ok(cursor_options) = cursor_query.single()
+        && cursor_options.grab_mode != CursorGrabMode::Locked
+    {
+        for _ in mouse_motion.read() {}
+        return;
     }
 
     if let (Ok(mut player_transform), Ok(mut camera_transform)) =
@@ -488,12 +544,12 @@ fn release_cursor(cursor_options: &mut CursorOptions) {
 }
 
 fn raycast_solid_voxel(
-    world: &World,
-    chunk_query: &Query<&crate::world::Chunk>,
+    voxels: &VoxelReader,
+    blocks: &BlockRegistry,
     start: Vec3,
     direction: Vec3,
     max_distance: f32,
-) -> Option<(Vec3, Vec3)> {
+) -> Option<(IVec3, Vec3)> {
     let ray_dir = direction.normalize_or_zero();
     if ray_dir == Vec3::ZERO {
         return None;
@@ -519,11 +575,11 @@ fn raycast_solid_voxel(
     let mut distance_traveled = 0.0;
 
     while distance_traveled <= max_distance {
-        let sample_pos = voxel_center_from_indices(voxel_x, voxel_y, voxel_z);
-        if let Some(voxel) = world.get_voxel_at_world(sample_pos, chunk_query) {
-            if voxel.is_solid() {
-                return Some((sample_pos, last_hit_normal.unwrap_or(-ray_dir.signum())));
-            }
+        let cell = IVec3::new(voxel_x, voxel_y, voxel_z);
+        if let Some(voxel) = voxels.get(cell)
+            && blocks.is_solid(voxel.block)
+        {
+            return Some((cell, last_hit_normal.unwrap_or(-ray_dir.signum())));
         }
 
         if t_max_x <= t_max_y && t_max_x <= t_max_z {
@@ -565,37 +621,29 @@ fn initial_axis_distance(origin: f32, direction: f32, voxel_index: i32) -> f32 {
     }
 }
 
-fn voxel_center_from_indices(x: i32, y: i32, z: i32) -> Vec3 {
-    Vec3::new(
-        (x as f32 + 0.5) * VOXEL_SIZE,
-        (y as f32 + 0.5) * VOXEL_SIZE,
-        (z as f32 + 0.5) * VOXEL_SIZE,
-    )
-}
-
 fn voxel_selection(
     mut interaction: ResMut<PlayerInteraction>,
-    world: Res<World>,
+    voxels: VoxelReader,
     camera_query: Query<&GlobalTransform, (With<PlayerCamera>, Without<Player>)>,
-    chunk_query: Query<&crate::world::Chunk>,
+    blocks: Res<BlockRegistry>,
 ) {
     if let Ok(camera_transform) = camera_query.single() {
         let camera_pos = camera_transform.translation();
         let camera_forward = camera_transform.forward();
 
         match raycast_solid_voxel(
-            &world,
-            &chunk_query,
+            &voxels,
+            &blocks,
             camera_pos,
             *camera_forward,
             interaction.interaction_range,
         ) {
-            Some((voxel_pos, normal)) => {
-                interaction.selected_voxel_world_pos = Some(voxel_pos);
+            Some((cell, normal)) => {
+                interaction.selected_cell = Some(cell);
                 interaction.hit_face = VoxelFace::from_normal(normal);
             }
             None => {
-                interaction.selected_voxel_world_pos = None;
+                interaction.selected_cell = None;
                 interaction.hit_face = None;
             }
         }
@@ -606,20 +654,23 @@ fn material_selection_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut mouse_wheel: MessageReader<MouseWheel>,
     mut interaction: ResMut<PlayerInteraction>,
+    blocks: Res<BlockRegistry>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Digit1) {
-        set_selected_material_by_index(&mut interaction, 0);
+    let hotbar = blocks.hotbar_blocks();
+    if hotbar.is_empty() {
         return;
     }
 
-    if keyboard_input.just_pressed(KeyCode::Digit2) {
-        set_selected_material_by_index(&mut interaction, 1);
-        return;
+    if !hotbar.contains(&interaction.selected_material) {
+        let index = selected_material_index(hotbar, interaction.selected_material);
+        set_selected_material_by_index(&mut interaction, hotbar, index);
     }
 
-    if keyboard_input.just_pressed(KeyCode::Digit3) {
-        set_selected_material_by_index(&mut interaction, 2);
-        return;
+    for (index, key) in HOTBAR_KEYS.iter().enumerate().take(hotbar.len()) {
+        if keyboard_input.just_pressed(*key) {
+            set_selected_material_by_index(&mut interaction, hotbar, index);
+            return;
+        }
     }
 
     let scroll_delta = mouse_wheel.read().map(|event| event.y).sum::<f32>();
@@ -627,42 +678,39 @@ fn material_selection_input(
         return;
     }
 
-    let current_index = selected_material_index(interaction.selected_material) as i32;
-    let material_count = HOTBAR_MATERIALS.len() as i32;
+    let current_index = selected_material_index(hotbar, interaction.selected_material) as i32;
+    let material_count = hotbar.len() as i32;
     let direction = if scroll_delta > 0.0 { -1 } else { 1 };
     let next_index = (current_index + direction).rem_euclid(material_count) as usize;
-    set_selected_material_by_index(&mut interaction, next_index);
+    set_selected_material_by_index(&mut interaction, hotbar, next_index);
 }
 
-fn calculate_placement_position(voxel_center: Vec3, face: VoxelFace) -> Vec3 {
+fn calculate_placement_cell(cell: IVec3, face: VoxelFace) -> IVec3 {
     let (dx, dy, dz) = face.get_offset();
-    voxel_center + Vec3::new(dx as f32, dy as f32, dz as f32) * VOXEL_SIZE
+    cell + IVec3::new(dx, dy, dz)
 }
 
 pub fn brush_world_size() -> f32 {
     VOXEL_SIZE
 }
 
-pub fn brush_preview_origin(center: Vec3) -> Vec3 {
-    center - Vec3::splat(VOXEL_SIZE / 2.0)
+pub fn brush_preview_origin(cell: IVec3) -> Vec3 {
+    voxel_cell_origin(cell)
 }
 
-pub fn placement_center(selected_voxel_center: Vec3, hit_face: VoxelFace) -> Vec3 {
-    calculate_placement_position(selected_voxel_center, hit_face)
+pub fn placement_cell(selected_cell: IVec3, hit_face: VoxelFace) -> IVec3 {
+    calculate_placement_cell(selected_cell, hit_face)
 }
 
-pub fn brush_center_for_edit(
-    selected_voxel_center: Vec3,
-    hit_face: Option<VoxelFace>,
-) -> Option<Vec3> {
-    hit_face.map(|face| placement_center(selected_voxel_center, face))
+pub fn brush_cell_for_edit(selected_cell: IVec3, hit_face: Option<VoxelFace>) -> Option<IVec3> {
+    hit_face.map(|face| placement_cell(selected_cell, face))
 }
 
-fn player_overlaps_voxel(player_pos: Vec3, voxel_center: Vec3) -> bool {
+fn player_overlaps_voxel(player_pos: Vec3, cell: IVec3) -> bool {
     let player_min = player_pos + Vec3::new(-0.25, 0.0, -0.25);
     let player_max = player_pos + Vec3::new(0.25, 2.0, 0.25);
-    let voxel_min = voxel_center - Vec3::splat(VOXEL_SIZE / 2.0);
-    let voxel_max = voxel_center + Vec3::splat(VOXEL_SIZE / 2.0);
+    let voxel_min = voxel_cell_origin(cell);
+    let voxel_max = voxel_min + Vec3::splat(VOXEL_SIZE);
 
     player_min.x < voxel_max.x
         && player_max.x > voxel_min.x
@@ -672,29 +720,33 @@ fn player_overlaps_voxel(player_pos: Vec3, voxel_center: Vec3) -> bool {
         && player_max.z > voxel_min.z
 }
 
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Bevy system reading input, cursor, cooldown and world state"
+)]
 fn voxel_interaction(
     interaction: Res<PlayerInteraction>,
     mouse_input: Res<ButtonInput<MouseButton>>,
     cursor_query: Query<&CursorOptions, With<PrimaryWindow>>,
-    chunk_query: Query<&crate::world::Chunk>,
-    world: Res<World>,
+    voxels: VoxelReader,
     player_query: Query<&Transform, With<Player>>,
     time: Res<Time>,
     mut placement_cooldown: ResMut<PlacementCooldown>,
     mut edit_writer: MessageWriter<EditRequest>,
+    blocks: Res<BlockRegistry>,
 ) {
-    if let Ok(cursor_options) = cursor_query.single() {
-        if cursor_options.grab_mode != CursorGrabMode::Locked {
-            return;
-        }
+    if let Ok(cursor_options) = cursor_query.single()
+        && cursor_options.grab_mode != CursorGrabMode::Locked
+    {
+        return;
     }
 
-    let Some(selected_voxel_pos) = interaction.selected_voxel_world_pos else {
+    let Some(selected_cell) = interaction.selected_cell else {
         return;
     };
 
     if mouse_input.just_pressed(MouseButton::Left) {
-        let operations = create_break_operations(selected_voxel_pos, &world, &chunk_query);
+        let operations = create_break_operations(selected_cell, &voxels, &blocks);
 
         if !operations.is_empty() {
             queue_edit_request(operations, &mut edit_writer);
@@ -709,10 +761,10 @@ fn voxel_interaction(
 
         if let Ok(player_transform) = player_query.single() {
             let operations = create_place_operations(
-                selected_voxel_pos,
+                selected_cell,
                 player_transform.translation,
-                &world,
-                &chunk_query,
+                &voxels,
+                &blocks,
                 interaction.selected_material,
                 interaction.hit_face,
             );
@@ -726,53 +778,60 @@ fn voxel_interaction(
 }
 
 fn create_break_operations(
-    selected_voxel_pos: Vec3,
-    world: &World,
-    chunk_query: &Query<&crate::world::Chunk>,
+    selected_cell: IVec3,
+    voxels: &VoxelReader,
+    blocks: &BlockRegistry,
 ) -> Vec<AppliedEditOperation> {
-    [selected_voxel_pos]
+    [selected_cell]
         .into_iter()
-        .filter_map(|target_pos| {
-            world
-                .get_voxel_at_world(target_pos, chunk_query)
-                .and_then(|existing| {
-                    existing.is_solid().then_some(AppliedEditOperation {
-                        position: target_pos,
-                        after: VoxelType::Air,
+        .filter_map(|target_cell| {
+            voxels.get(target_cell).and_then(|existing| {
+                blocks
+                    .is_solid(existing.block)
+                    .then_some(AppliedEditOperation {
+                        cell: target_cell,
+                        after: Voxel::new(BlockId::AIR),
                     })
-                })
+            })
         })
         .collect()
 }
 
 fn create_place_operations(
-    selected_voxel_pos: Vec3,
+    selected_cell: IVec3,
     player_pos: Vec3,
-    world: &World,
-    chunk_query: &Query<&crate::world::Chunk>,
-    selected_material: VoxelType,
+    voxels: &VoxelReader,
+    blocks: &BlockRegistry,
+    selected_material: BlockId,
     hit_face: Option<VoxelFace>,
 ) -> Vec<AppliedEditOperation> {
-    let Some(place_center) = brush_center_for_edit(selected_voxel_pos, hit_face) else {
+    let Some(place_cell) = brush_cell_for_edit(selected_cell, hit_face) else {
         return Vec::new();
     };
 
-    [place_center]
+    [place_cell]
         .into_iter()
-        .filter(|target_pos| !player_overlaps_voxel(player_pos, *target_pos))
-        .filter_map(|target_pos| {
-            world
-                .get_voxel_at_world(target_pos, chunk_query)
-                .and_then(|existing| {
-                    (!existing.is_solid()).then_some(AppliedEditOperation {
-                        position: target_pos,
-                        after: selected_material,
-                    })
+        .filter(|target_cell| !player_overlaps_voxel(player_pos, *target_cell))
+        .filter_map(|target_cell| {
+            voxels.get(target_cell).and_then(|existing| {
+                (!blocks.is_solid(existing.block)).then_some(AppliedEditOperation {
+                    cell: target_cell,
+                    after: placed_voxel(blocks, selected_material, hit_face),
                 })
+            })
         })
         .collect()
 }
 
+/// Orients blocks that declare a `facing` property toward the face they were placed against.
+fn placed_voxel(blocks: &BlockRegistry, block: BlockId, hit_face: Option<VoxelFace>) -> Voxel {
+    let voxel = Voxel::new(block);
+    match hit_face {
+        Some(face) => blocks.with_state_value(voxel, "facing", face.name()),
+        None => voxel,
+    }
+}
+
 fn queue_edit_request(
     operations: Vec<AppliedEditOperation>,
     edit_writer: &mut MessageWriter<EditRequest>,
@@ -780,9 +839,9 @@ fn queue_edit_request(
     let request_operations = operations
         .iter()
         .map(|operation| EditOperation {
-            position: operation.position,
-            voxel_type: operation.after,
-            mode: if operation.after == VoxelType::Air {
+            cell: operation.cell,
+            voxel: operation.after,
+            mode: if operation.after.block.is_air() {
                 EditMode::Break
             } else {
                 EditMode::Place
@@ -790,28 +849,28 @@ fn queue_edit_request(
         })
         .collect::<Vec<_>>();
 
-    let positions = request_operations
+    let cells = request_operations
         .iter()
-        .map(|operation| operation.position)
+        .map(|operation| operation.cell)
         .collect::<Vec<_>>();
 
     edit_writer.write(EditRequest {
-        positions,
+        cells,
         operations: request_operations,
     });
 }
 
 fn player_unstuck(
     mut player_query: Query<&mut Transform, With<Player>>,
-    world: Res<World>,
-    chunk_query: Query<&crate::world::Chunk>,
+    voxels: VoxelReader,
+    blocks: Res<BlockRegistry>,
 ) {
     let Ok(mut player_transform) = player_query.single_mut() else {
         return;
     };
 
     let current_position = player_transform.translation;
-    if !player_intersects_solid_voxel(&world, &chunk_query, current_position) {
+    if !player_intersects_solid_voxel(&voxels, &blocks, current_position) {
         return;
     }
 
@@ -819,7 +878,7 @@ fn player_unstuck(
         let vertical_offset = Vec3::Y * (up_steps as f32 * VOXEL_SIZE);
         for horizontal_offset in unstuck_horizontal_offsets() {
             let candidate_position = current_position + vertical_offset + horizontal_offset;
-            if !player_intersects_solid_voxel(&world, &chunk_query, candidate_position) {
+            if !player_intersects_solid_voxel(&voxels, &blocks, candidate_position) {
                 player_transform.translation = candidate_position;
                 return;
             }
@@ -828,35 +887,19 @@ fn player_unstuck(
 }
 
 fn player_intersects_solid_voxel(
-    world: &World,
-    chunk_query: &Query<&crate::world::Chunk>,
+    voxels: &VoxelReader,
+    blocks: &BlockRegistry,
     player_position: Vec3,
 ) -> bool {
     let player_min = player_position + Vec3::new(-0.25, 0.0, -0.25);
     let player_max = player_position + Vec3::new(0.25, 2.0, 0.25);
 
-    let min_x = (player_min.x / VOXEL_SIZE).floor() as i32;
-    let min_y = (player_min.y / VOXEL_SIZE).floor() as i32;
-    let min_z = (player_min.z / VOXEL_SIZE).floor() as i32;
-    let max_x = ((player_max.x - f32::EPSILON) / VOXEL_SIZE).floor() as i32;
-    let max_y = ((player_max.y - f32::EPSILON) / VOXEL_SIZE).floor() as i32;
-    let max_z = ((player_max.z - f32::EPSILON) / VOXEL_SIZE).floor() as i32;
-
-    for voxel_x in min_x..=max_x {
-        for voxel_y in min_y..=max_y {
-            for voxel_z in min_z..=max_z {
-                let voxel_center = voxel_center_from_indices(voxel_x, voxel_y, voxel_z);
-                if world
-                    .get_voxel_at_world(voxel_center, chunk_query)
-                    .is_some_and(|voxel| voxel.is_solid())
-                {
-                    return true;
-                }
-            }
-        }
-    }
-
-    false
+    voxels
+        .region(
+            voxel_cell(player_min),
+            voxel_cell(player_max - Vec3::splat(f32::EPSILON)),
+        )
+        .any(|(_, voxel)| blocks.is_solid(voxel.block))
 }
 
 fn unstuck_horizontal_offsets() -> [Vec3; 5] {
@@ -4,13 +4,15 @@ use bevy::prelude::*;
 use bevy::tasks::{AsyncComputeTaskPool, Task, futures_lite::future};
 
 use crate::AppState;
+use crate::block::BlockRegistry;
+use crate::fluid::{fluid_level_count, fluid_state};
+use crate::light::{LightChannel, light_brightness};
 use crate::player::{
-    PlayerInteraction, brush_center_for_edit, brush_preview_origin, brush_world_size,
+    PlayerInteraction, brush_cell_for_edit, brush_preview_origin, brush_world_size,
 };
-use crate::voxel::{VOXEL_SIZE, VoxelFace, VoxelType};
+use crate::voxel::{BlockId, VOXEL_SIZE, Voxel, VoxelFace};
 use crate::world::{
-    CHUNK_VOXELS_HEIGHT, CHUNK_VOXELS_SIZE, Chunk, ChunkCoord, World, chunk_world_height,
-    chunk_world_origin,
+    CHUNK_VOXELS_SIZE, Chunk, ChunkCoord, ViewDistanceSettings, VoxelAccess, VoxelReader, World,
 };
 
 #[derive(Component)]
@@ -22,19 +24,45 @@ pub struct VoxelHighlight;
 #[derive(Component)]
 pub struct Crosshair;
 
+/// Child of a chunk entity that draws the chunk's translucent voxels with alpha blending.
 #[derive(Component)]
-pub(crate) struct PendingRenderMesh(Task<(u64, Option<Mesh>)>);
+pub struct ChunkTransparentMesh;
+
+/// Points a chunk entity at its `ChunkTransparentMesh` child.
+#[derive(Component)]
+pub(crate) struct TransparentMeshEntity(Entity);
+
+#[derive(Component)]
+pub(crate) struct PendingRenderMesh(Task<((u64, u64), ChunkMeshes)>);
+
+#[derive(Default)]
+struct ChunkMeshes {
+    opaque: Option<Mesh>,
+    transparent: Option<Mesh>,
+}
+
+#[derive(Default)]
+struct ChunkMeshBuffers {
+    vertices: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+}
 
 #[derive(Clone)]
 struct ChunkRenderInput {
     chunk: Chunk,
-    neighbors: HorizontalChunkNeighbors,
+    blocks: BlockRegistry,
+    neighbors: ChunkNeighbors,
 }
 
 #[derive(Clone, Default)]
-struct HorizontalChunkNeighbors {
+struct ChunkNeighbors {
     negative_x: Option<Chunk>,
     positive_x: Option<Chunk>,
+    negative_y: Option<Chunk>,
+    positive_y: Option<Chunk>,
     negative_z: Option<Chunk>,
     positive_z: Option<Chunk>,
 }
@@ -44,6 +72,7 @@ pub struct RenderPlugin;
 #[derive(Resource)]
 struct ChunkMaterial {
     handle: Handle<StandardMaterial>,
+    transparent_handle: Handle<StandardMaterial>,
 }
 
 const SUN_ILLUMINANCE: f32 = 14_000.0;
@@ -65,6 +94,7 @@ impl Plugin for RenderPlugin {
             (
                 queue_chunk_render_builds.before(process_chunk_render_builds),
                 process_chunk_render_builds,
+                sync_chunk_visibility.after(process_chunk_render_builds),
                 voxel_highlight_system,
             )
                 .run_if(in_state(AppState::LoadingWorld).or(in_state(AppState::InGame))),
@@ -103,8 +133,19 @@ fn setup_chunk_material(mut commands: Commands, mut materials: ResMut<Assets<Sta
         reflectance: 0.08,
         ..default()
     });
+    let transparent_material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        alpha_mode: AlphaMode::Blend,
+        metallic: 0.0,
+        perceptual_roughness: 0.3,
+        reflectance: 0.3,
+        ..default()
+    });
 
-    commands.insert_resource(ChunkMaterial { handle: material });
+    commands.insert_resource(ChunkMaterial {
+        handle: material,
+        transparent_handle: transparent_material,
+    });
 }
 
 fn setup_crosshair(mut commands: Commands) {
@@ -192,6 +233,10 @@ fn cleanup_ingame_render_ui(
     }
 }
 
+#[allow(
+    clippy::type_complexity,
+    reason = "the query filter pairs the refresh marker with the in-flight build marker"
+)]
 fn queue_chunk_render_builds(
     mut commands: Commands,
     world: Res<World>,
@@ -203,15 +248,22 @@ fn queue_chunk_render_builds(
         ),
     >,
     all_chunks: Query<&Chunk>,
+    blocks: Res<BlockRegistry>,
+    view: Res<ViewDistanceSettings>,
 ) {
     let task_pool = AsyncComputeTaskPool::get();
 
     for (entity, chunk) in chunk_query.iter() {
+        if !world.within_distance(chunk.coord, view.render_distance_chunks) {
+            continue;
+        }
+
         let input = ChunkRenderInput {
             chunk: chunk.clone(),
-            neighbors: gather_horizontal_neighbors(chunk.coord, &world, &all_chunks),
+            blocks: blocks.clone(),
+            neighbors: gather_chunk_neighbors(chunk.coord, &world, &all_chunks),
         };
-        let revision = input.chunk.revision;
+        let revision = (input.chunk.revision, input.chunk.light_revision);
         let task = task_pool.spawn(async move { (revision, generate_chunk_mesh(&input)) });
         commands.entity(entity).insert(PendingRenderMesh(task));
     }
@@ -221,45 +273,87 @@ fn process_chunk_render_builds(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     chunk_material: Res<ChunkMaterial>,
-    mut chunk_query: Query<(Entity, &mut PendingRenderMesh, &Chunk)>,
+    mut chunk_query: Query<(
+        Entity,
+        &mut PendingRenderMesh,
+        &Chunk,
+        Option<&TransparentMeshEntity>,
+    )>,
 ) {
-    for (entity, mut pending_mesh, chunk) in chunk_query.iter_mut() {
-        let Some((revision, mesh)) = future::block_on(future::poll_once(&mut pending_mesh.0))
+    for (entity, mut pending_mesh, chunk, transparent_entity) in chunk_query.iter_mut() {
+        let Some((revision, chunk_meshes)) =
+            future::block_on(future::poll_once(&mut pending_mesh.0))
         else {
             continue;
         };
 
-        if revision != chunk.revision {
+        if revision != (chunk.revision, chunk.light_revision) {
             commands.entity(entity).remove::<PendingRenderMesh>();
             continue;
         }
 
-        if let Some(mesh) = mesh {
-            let mesh_handle = meshes.add(mesh);
-            let chunk_world_pos = chunk_world_origin(chunk.coord);
+        let mut entity_commands = commands.entity(entity);
+        entity_commands
+            .remove::<PendingRenderMesh>()
+            .remove::<crate::player::NeedsRenderRefresh>()
+            .insert(Visibility::Visible);
 
-            let mut entity_commands = commands.entity(entity);
-            entity_commands.remove::<PendingRenderMesh>();
-            entity_commands.remove::<crate::player::NeedsRenderRefresh>();
+        if let Some(mesh) = chunk_meshes.opaque {
             entity_commands.insert((
                 ChunkMesh,
-                Mesh3d(mesh_handle),
+                Mesh3d(meshes.add(mesh)),
                 MeshMaterial3d(chunk_material.handle.clone()),
-                Transform::from_translation(chunk_world_pos),
-                GlobalTransform::default(),
-                Visibility::Visible,
             ));
         } else {
-            commands
-                .entity(entity)
-                .remove::<PendingRenderMesh>()
-                .remove::<crate::player::NeedsRenderRefresh>()
+            entity_commands
                 .remove::<ChunkMesh>()
                 .remove::<Mesh3d>()
                 .remove::<MeshMaterial3d<StandardMaterial>>();
         }
 
-        commands.entity(entity).remove::<PendingRenderMesh>();
+        match (chunk_meshes.transparent, transparent_entity) {
+            (Some(mesh), Some(transparent_entity)) => {
+                commands
+                    .entity(transparent_entity.0)
+                    .insert(Mesh3d(meshes.add(mesh)));
+            }
+            (Some(mesh), None) => {
+                let child = commands
+                    .spawn((
+                        ChunkTransparentMesh,
+                        Mesh3d(meshes.add(mesh)),
+                        MeshMaterial3d(chunk_material.transparent_handle.clone()),
+                        Transform::default(),
+                        Visibility::Inherited,
+                        ChildOf(entity),
+                    ))
+                    .id();
+                commands.entity(entity).insert(TransparentMeshEntity(child));
+            }
+            (None, Some(transparent_entity)) => {
+                commands.entity(transparent_entity.0).despawn();
+                commands.entity(entity).remove::<TransparentMeshEntity>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Hides chunks that stay loaded past the render distance for simulation, and shows them again
+/// once they are back in range.
+fn sync_chunk_visibility(
+    world: Res<World>,
+    view: Res<ViewDistanceSettings>,
+    mut chunk_query: Query<(&Chunk, &mut Visibility)>,
+) {
+    for (chunk, mut visibility) in chunk_query.iter_mut() {
+        visibility.set_if_neq(
+            if world.within_distance(chunk.coord, view.render_distance_chunks) {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            },
+        );
     }
 }
 
@@ -270,8 +364,8 @@ fn voxel_highlight_system(
         With<VoxelHighlight>,
     >,
     mut meshes: ResMut<Assets<Mesh>>,
-    chunk_query: Query<&crate::world::Chunk>,
-    world: Res<crate::world::World>,
+    voxels: VoxelReader,
+    blocks: Res<BlockRegistry>,
 ) {
     let Ok((mut highlight_transform, mut highlight_visibility, mut highlight_mesh)) =
         highlight_query.single_mut()
@@ -279,24 +373,21 @@ fn voxel_highlight_system(
         return;
     };
 
-    if let Some(selected_voxel_pos) = interaction.selected_voxel_world_pos {
-        if let Some(voxel) = world.get_voxel_at_world(selected_voxel_pos, &chunk_query) {
-            if voxel.is_solid() {
-                let Some(preview_center) =
-                    brush_center_for_edit(selected_voxel_pos, interaction.hit_face)
-                else {
-                    *highlight_visibility = Visibility::Hidden;
-                    return;
-                };
+    if let Some(selected_cell) = interaction.selected_cell
+        && let Some(voxel) = voxels.get(selected_cell)
+        && blocks.is_solid(voxel.block)
+    {
+        let Some(preview_cell) = brush_cell_for_edit(selected_cell, interaction.hit_face) else {
+            *highlight_visibility = Visibility::Hidden;
+            return;
+        };
 
-                let mesh = create_box_wireframe(Vec3::splat(brush_world_size()));
+        let mesh = create_box_wireframe(Vec3::splat(brush_world_size()));
 
-                highlight_mesh.0 = meshes.add(mesh);
-                highlight_transform.translation = brush_preview_origin(preview_center);
-                *highlight_visibility = Visibility::Visible;
-                return;
-            }
-        }
+        highlight_mesh.0 = meshes.add(mesh);
+        highlight_transform.translation = brush_preview_origin(preview_cell);
+        *highlight_visibility = Visibility::Visible;
+        return;
     }
 
     *highlight_visibility = Visibility::Hidden;
@@ -336,154 +427,143 @@ fn create_box_wireframe(size: Vec3) -> Mesh {
     mesh
 }
 
-fn generate_chunk_mesh(input: &ChunkRenderInput) -> Option<Mesh> {
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
-    let mut normals = Vec::new();
-    let mut uvs = Vec::new();
-    let mut colors = Vec::new();
+fn generate_chunk_mesh(input: &ChunkRenderInput) -> ChunkMeshes {
+    if input.chunk.is_empty() {
+        return ChunkMeshes::default();
+    }
+
+    let mut opaque = ChunkMeshBuffers::default();
+    let mut transparent = ChunkMeshBuffers::default();
 
     for x in 0..CHUNK_VOXELS_SIZE {
-        for y in 0..CHUNK_VOXELS_HEIGHT {
+        for y in 0..CHUNK_VOXELS_SIZE {
             for z in 0..CHUNK_VOXELS_SIZE {
-                if let Some(voxel) = input.chunk.get_voxel(x, y, z) {
-                    if voxel.is_solid() {
-                        let local_pos = Vec3::new(
-                            x as f32 * VOXEL_SIZE,
-                            y as f32 * VOXEL_SIZE,
-                            z as f32 * VOXEL_SIZE,
-                        );
-
-                        add_voxel_faces(
-                            &mut vertices,
-                            &mut indices,
-                            &mut normals,
-                            &mut uvs,
-                            &mut colors,
-                            local_pos,
-                            voxel.voxel_type,
-                            input,
-                            x,
-                            y,
-                            z,
-                        );
-                    }
+                let Some(voxel) = input.chunk.get_voxel(x, y, z) else {
+                    continue;
+                };
+                if voxel.block.is_air() {
+                    continue;
                 }
+
+                let local_pos = Vec3::new(
+                    x as f32 * VOXEL_SIZE,
+                    y as f32 * VOXEL_SIZE,
+                    z as f32 * VOXEL_SIZE,
+                );
+                let buffers = if input.blocks.is_opaque(voxel.block) {
+                    &mut opaque
+                } else {
+                    &mut transparent
+                };
+
+                add_voxel_faces(buffers, local_pos, voxel, input, x, y, z);
             }
         }
     }
 
-    if vertices.is_empty() {
-        return None;
+    ChunkMeshes {
+        opaque: opaque.into_mesh(),
+        transparent: transparent.into_mesh(),
     }
+}
 
-    let chunk_size_world = CHUNK_VOXELS_SIZE as f32 * VOXEL_SIZE;
-    let chunk_height_world = chunk_world_height();
-
-    let mut extended_vertices = vertices;
-    let dummy_indices_start = extended_vertices.len() as u32;
-
-    extended_vertices.extend_from_slice(&[
-        [0.0, 0.0, 0.0],
-        [chunk_size_world, 0.0, 0.0],
-        [0.0, 0.0, chunk_size_world],
-        [chunk_size_world, 0.0, chunk_size_world],
-        [0.0, chunk_height_world, 0.0],
-        [chunk_size_world, chunk_height_world, 0.0],
-        [0.0, chunk_height_world, chunk_size_world],
-        [chunk_size_world, chunk_height_world, chunk_size_world],
-    ]);
+impl ChunkMeshBuffers {
+    fn into_mesh(self) -> Option<Mesh> {
+        if self.vertices.is_empty() {
+            return None;
+        }
 
-    let mut extended_normals = normals;
-    let mut extended_uvs = uvs;
-    let mut extended_colors = colors;
-    extended_normals.extend_from_slice(&[[0.0, 1.0, 0.0]; 8]);
-    extended_uvs.extend_from_slice(&[[0.0, 0.0]; 8]);
-    extended_colors.extend_from_slice(&[[1.0, 1.0, 1.0, 1.0]; 8]);
-
-    let mut extended_indices = indices;
-    for i in 0..8 {
-        let idx = dummy_indices_start + i;
-        extended_indices.extend_from_slice(&[idx, idx, idx]);
-    }
+        let chunk_size_world = CHUNK_VOXELS_SIZE as f32 * VOXEL_SIZE;
+
+        let mut extended_vertices = self.vertices;
+        let dummy_indices_start = extended_vertices.len() as u32;
+
+        extended_vertices.extend_from_slice(&[
+            [0.0, 0.0, 0.0],
+            [chunk_size_world, 0.0, 0.0],
+            [0.0, 0.0, chunk_size_world],
+            [chunk_size_world, 0.0, chunk_size_world],
+            [0.0, chunk_size_world, 0.0],
+            [chunk_size_world, chunk_size_world, 0.0],
+            [0.0, chunk_size_world, chunk_size_world],
+            [chunk_size_world, chunk_size_world, chunk_size_world],
+        ]);
+
+        let mut extended_normals = self.normals;
+        let mut extended_uvs = self.uvs;
+        let mut extended_colors = self.colors;
+        extended_normals.extend_from_slice(&[[0.0, 1.0, 0.0]; 8]);
+        extended_uvs.extend_from_slice(&[[0.0, 0.0]; 8]);
+        extended_colors.extend_from_slice(&[[1.0, 1.0, 1.0, 1.0]; 8]);
+
+        let mut extended_indices = self.indices;
+        for i in 0..8 {
+            let idx = dummy_indices_start + i;
+            extended_indices.extend_from_slice(&[idx, idx, idx]);
+        }
 
-    let mut mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::RENDER_WORLD,
-    );
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD,
+        );
 
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, extended_vertices);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, extended_normals);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, extended_uvs);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, extended_colors);
-    mesh.insert_indices(Indices::U32(extended_indices));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, extended_vertices);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, extended_normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, extended_uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, extended_colors);
+        mesh.insert_indices(Indices::U32(extended_indices));
 
-    Some(mesh)
+        Some(mesh)
+    }
 }
 
 fn add_voxel_faces(
-    vertices: &mut Vec<[f32; 3]>,
-    indices: &mut Vec<u32>,
-    normals: &mut Vec<[f32; 3]>,
-    uvs: &mut Vec<[f32; 2]>,
-    colors: &mut Vec<[f32; 4]>,
+    buffers: &mut ChunkMeshBuffers,
     pos: Vec3,
-    voxel_type: VoxelType,
+    voxel: Voxel,
     input: &ChunkRenderInput,
     x: usize,
     y: usize,
     z: usize,
 ) {
+    let block = voxel.block;
+    let height = voxel_surface_height(input, voxel, x, y, z);
     let faces = [
-        (
-            should_render_face(input, x, y, z, -1, 0, 0),
-            VoxelFace::NegativeX,
-        ),
-        (
-            should_render_face(input, x, y, z, 1, 0, 0),
-            VoxelFace::PositiveX,
-        ),
-        (
-            should_render_face(input, x, y, z, 0, -1, 0),
-            VoxelFace::NegativeY,
-        ),
-        (
-            should_render_face(input, x, y, z, 0, 1, 0),
-            VoxelFace::PositiveY,
-        ),
-        (
-            should_render_face(input, x, y, z, 0, 0, -1),
-            VoxelFace::NegativeZ,
-        ),
-        (
-            should_render_face(input, x, y, z, 0, 0, 1),
-            VoxelFace::PositiveZ,
-        ),
+        VoxelFace::NegativeX,
+        VoxelFace::PositiveX,
+        VoxelFace::NegativeY,
+        VoxelFace::PositiveY,
+        VoxelFace::NegativeZ,
+        VoxelFace::PositiveZ,
     ];
 
-    for (should_render, face) in faces.iter() {
-        if *should_render {
-            add_face(
-                vertices, indices, normals, uvs, colors, pos, *face, voxel_type,
-            );
+    for face in faces {
+        let (dx, dy, dz) = face.get_offset();
+        let (nx, ny, nz) = (x as i32 + dx, y as i32 + dy, z as i32 + dz);
+        if !should_render_face(input, block, nx, ny, nz) {
+            continue;
         }
+
+        let brightness = light_brightness(
+            neighbor_light(input, LightChannel::Block, nx, ny, nz),
+            neighbor_light(input, LightChannel::Sky, nx, ny, nz),
+        );
+        add_face(
+            buffers,
+            pos,
+            face,
+            height,
+            input.blocks.color(block),
+            brightness,
+        );
     }
 }
 
-fn should_render_face(
-    input: &ChunkRenderInput,
-    x: usize,
-    y: usize,
-    z: usize,
-    dx: i32,
-    dy: i32,
-    dz: i32,
-) -> bool {
-    let nx = x as i32 + dx;
-    let ny = y as i32 + dy;
-    let nz = z as i32 + dz;
-
-    neighbor_voxel_for_face(input, nx, ny, nz).is_none_or(|voxel| !voxel.is_solid())
+/// A face is hidden behind an opaque neighbor, and between two voxels of the same translucent
+/// block so that a pane of glass or a canopy of leaves has no inner faces.
+fn should_render_face(input: &ChunkRenderInput, block: BlockId, nx: i32, ny: i32, nz: i32) -> bool {
+    neighbor_voxel_for_face(input, nx, ny, nz)
+        .is_none_or(|voxel| !input.blocks.is_opaque(voxel.block) && voxel.block != block)
 }
 
 fn neighbor_voxel_for_face(
@@ -492,66 +572,69 @@ fn neighbor_voxel_for_face(
     ny: i32,
     nz: i32,
 ) -> Option<crate::voxel::Voxel> {
-    if ny < 0 || ny >= CHUNK_VOXELS_HEIGHT as i32 {
-        return None;
-    }
-
-    if (0..CHUNK_VOXELS_SIZE as i32).contains(&nx) && (0..CHUNK_VOXELS_SIZE as i32).contains(&nz) {
-        return input
-            .chunk
-            .get_voxel(nx as usize, ny as usize, nz as usize)
-            .copied();
-    }
-
-    if nx < 0 {
-        return input
-            .neighbors
-            .negative_x
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(CHUNK_VOXELS_SIZE - 1, ny as usize, nz as usize))
-            .copied();
-    }
-
-    if nx >= CHUNK_VOXELS_SIZE as i32 {
-        return input
-            .neighbors
-            .positive_x
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(0, ny as usize, nz as usize))
-            .copied();
-    }
+    let (chunk, x, y, z) = neighbor_chunk_for_face(input, nx, ny, nz)?;
+    chunk.get_voxel(x, y, z)
+}
 
-    if nz < 0 {
-        return input
-            .neighbors
-            .negative_z
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(nx as usize, ny as usize, CHUNK_VOXELS_SIZE - 1))
-            .copied();
-    }
+/// Light of the voxel a face looks into, which is the light the face receives.
+fn neighbor_light(
+    input: &ChunkRenderInput,
+    channel: LightChannel,
+    nx: i32,
+    ny: i32,
+    nz: i32,
+) -> u8 {
+    neighbor_chunk_for_face(input, nx, ny, nz)
+        .map_or(0, |(chunk, x, y, z)| chunk.light(channel, x, y, z))
+}
 
-    if nz >= CHUNK_VOXELS_SIZE as i32 {
-        return input
-            .neighbors
-            .positive_z
-            .as_ref()
-            .and_then(|chunk| chunk.get_voxel(nx as usize, ny as usize, 0))
-            .copied();
-    }
+/// Resolves chunk-local coordinates that may step one voxel outside the chunk to the chunk that
+/// holds them.
+fn neighbor_chunk_for_face(
+    input: &ChunkRenderInput,
+    nx: i32,
+    ny: i32,
+    nz: i32,
+) -> Option<(&Chunk, usize, usize, usize)> {
+    let size = CHUNK_VOXELS_SIZE as i32;
+    let neighbor = if nx < 0 {
+        &input.neighbors.negative_x
+    } else if nx >= size {
+        &input.neighbors.positive_x
+    } else if ny < 0 {
+        &input.neighbors.negative_y
+    } else if ny >= size {
+        &input.neighbors.positive_y
+    } else if nz < 0 {
+        &input.neighbors.negative_z
+    } else if nz >= size {
+        &input.neighbors.positive_z
+    } else {
+        return Some((&input.chunk, nx as usize, ny as usize, nz as usize));
+    };
 
-    None
+    neighbor.as_ref().map(|chunk| {
+        (
+            chunk,
+            nx.rem_euclid(size) as usize,
+            ny.rem_euclid(size) as usize,
+            nz.rem_euclid(size) as usize,
+        )
+    })
 }
 
-fn gather_horizontal_neighbors(
+fn gather_chunk_neighbors(
     coord: ChunkCoord,
     world: &World,
     chunk_query: &Query<&Chunk>,
-) -> HorizontalChunkNeighbors {
-    HorizontalChunkNeighbors {
-        negative_x: get_chunk_clone(ChunkCoord::new(coord.x - 1, coord.z), world, chunk_query),
-        positive_x: get_chunk_clone(ChunkCoord::new(coord.x + 1, coord.z), world, chunk_query),
-        negative_z: get_chunk_clone(ChunkCoord::new(coord.x, coord.z - 1), world, chunk_query),
-        positive_z: get_chunk_clone(ChunkCoord::new(coord.x, coord.z + 1), world, chunk_query),
+) -> ChunkNeighbors {
+    ChunkNeighbors {
+        negative_x: get_chunk_clone(coord.offset(-1, 0, 0), world, chunk_query),
+        positive_x: get_chunk_clone(coord.offset(1, 0, 0), world, chunk_query),
+        negative_y: get_chunk_clone(coord.offset(0, -1, 0), world, chunk_query),
+        positive_y: get_chunk_clone(coord.offset(0, 1, 0), world, chunk_query),
+        negative_z: get_chunk_clone(coord.offset(0, 0, -1), world, chunk_query),
+        positive_z: get_chunk_clone(coord.offset(0, 0, 1), world, chunk_query),
     }
 }
 
@@ -563,28 +646,60 @@ fn get_chunk_clone(coord: ChunkCoord, world: &World, chunk_query: &Query<&Chunk>
         .cloned()
 }
 
+/// Fraction of the voxel its top face sits at. Fluids drop with each level away from their source
+/// unless more of the same fluid sits on top of them.
+fn voxel_surface_height(
+    input: &ChunkRenderInput,
+    voxel: Voxel,
+    x: usize,
+    y: usize,
+    z: usize,
+) -> f32 {
+    let Some((_, level)) = fluid_state(&input.blocks, voxel) else {
+        return 1.0;
+    };
+    let covered = neighbor_voxel_for_face(input, x as i32, y as i32 + 1, z as i32)
+        .is_some_and(|above| above.block == voxel.block);
+    if covered {
+        return 1.0;
+    }
+
+    let level_count = fluid_level_count(&input.blocks, voxel);
+    (level_count - level) as f32 / (level_count + 1) as f32
+}
+
 fn add_face(
-    vertices: &mut Vec<[f32; 3]>,
-    indices: &mut Vec<u32>,
-    normals: &mut Vec<[f32; 3]>,
-    uvs: &mut Vec<[f32; 2]>,
-    colors: &mut Vec<[f32; 4]>,
+    buffers: &mut ChunkMeshBuffers,
     pos: Vec3,
     face: VoxelFace,
-    voxel_type: VoxelType,
+    height: f32,
+    block_color: Color,
+    brightness: f32,
 ) {
-    let start_vertex = vertices.len() as u32;
-    let face_vertices = face.get_vertices(pos, VOXEL_SIZE);
+    let start_vertex = buffers.vertices.len() as u32;
+    let mut face_vertices = face.get_vertices(pos, VOXEL_SIZE);
+    for vertex in &mut face_vertices {
+        vertex[1] = pos.y + (vertex[1] - pos.y) * height;
+    }
     let face_normal = face.get_normal();
-    let linear = voxel_type.color().to_linear();
-    let color = [linear.red, linear.green, linear.blue, linear.alpha];
+    let linear = block_color.to_linear();
+    let color = [
+        linear.red * brightness,
+        linear.green * brightness,
+        linear.blue * brightness,
+        linear.alpha,
+    ];
 
-    vertices.extend_from_slice(&face_vertices);
-    normals.extend_from_slice(&[[face_normal.x, face_normal.y, face_normal.z]; 4]);
-    uvs.extend_from_slice(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
-    colors.extend_from_slice(&[color; 4]);
+    buffers.vertices.extend_from_slice(&face_vertices);
+    buffers
+        .normals
+        .extend_from_slice(&[[face_normal.x, face_normal.y, face_normal.z]; 4]);
+    buffers
+        .uvs
+        .extend_from_slice(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+    buffers.colors.extend_from_slice(&[color; 4]);
 
-    indices.extend_from_slice(&[
+    buffers.indices.extend_from_slice(&[
         start_vertex,
         start_vertex + 1,
         start_vertex + 2,
@@ -593,3 +708,28 @@ fn add_face(
         start_vertex + 3,
     ]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translucent_blocks_mesh_separately_without_inner_faces() {
+        let blocks = BlockRegistry::default();
+        let glass = Voxel::new(blocks.id_by_name("glass").unwrap());
+        let mut chunk = Chunk::new(ChunkCoord::new(0, 0, 0));
+        chunk.set_voxel(1, 1, 1, glass);
+        chunk.set_voxel(2, 1, 1, glass);
+
+        let meshes = generate_chunk_mesh(&ChunkRenderInput {
+            chunk,
+            blocks,
+            neighbors: ChunkNeighbors::default(),
+        });
+
+        assert!(meshes.opaque.is_none());
+        let transparent = meshes.transparent.unwrap();
+        // Two cubes sharing one face expose ten faces, plus the eight bounding-box vertices.
+        assert_eq!(transparent.count_vertices(), 10 * 4 + 8);
+    }
+}
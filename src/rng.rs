@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+/// Deterministic random stream for world generation. Seeding it from the world seed and a
+/// position gives every chunk that looks at the same place the same numbers.
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u32, key: u64) -> Self {
+        Self(mix(u64::from(seed) ^ mix(key)))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.0)
+    }
+
+    /// Uniform integer in `min..max`.
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        min + (self.next() % (max - min) as u64) as i32
+    }
+
+    /// Uniform value in `0.0..1.0`.
+    pub fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Hash key of a global cell, distinct for every cell.
+pub fn cell_key(cell: IVec3) -> u64 {
+    mix(u64::from(cell.x as u32))
+        ^ mix(u64::from(cell.y as u32) | 1 << 32)
+        ^ mix(u64::from(cell.z as u32) | 2 << 32)
+}
+
+/// SplitMix64 finalizer.
+pub fn mix(mut value: u64) -> u64 {
+    value ^= value >> 30;
+    value = value.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value ^= value >> 27;
+    value = value.wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
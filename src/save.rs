@@ -7,11 +7,14 @@ use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
 use bevy::tasks::{AsyncComputeTaskPool, Task, futures_lite::future};
 
+use crate::block::BlockRegistry;
+use crate::generator::WorldPreset;
 use crate::player::{Inventory, Player};
-use crate::voxel::VoxelType;
+use crate::terrain::TerrainSettings;
+use crate::voxel::{BlockId, Voxel};
 use crate::world::{Chunk, ChunkCoord};
 
-const SAVE_VERSION: u32 = 2;
+const SAVE_VERSION: u32 = 7;
 const DEFAULT_WORLD_SEED: u32 = 12345;
 const SAVE_MAGIC: &[u8; 4] = b"GECY";
 const CHUNK_MAGIC: &[u8; 4] = b"GCHK";
@@ -20,15 +23,17 @@ pub const DEFAULT_SAVE_ROOT: &str = "saves/default_world";
 #[derive(Clone)]
 pub struct SavedChunk {
     pub coord: ChunkCoord,
-    pub voxels: Vec<VoxelType>,
+    pub voxels: Vec<Voxel>,
 }
 
 #[derive(Clone)]
 struct WorldMetadata {
     version: u32,
     seed: u32,
+    preset: WorldPreset,
+    terrain: TerrainSettings,
     player_translation: [f32; 3],
-    inventory: Vec<(VoxelType, u32)>,
+    inventory: Vec<(BlockId, u32)>,
 }
 
 #[derive(Resource)]
@@ -36,6 +41,9 @@ pub struct SaveState {
     pub root: PathBuf,
     pub version: u32,
     pub seed: u32,
+    pub preset: WorldPreset,
+    /// Settings the world's noise terrain was created with.
+    pub terrain: TerrainSettings,
     pub edited_chunks: HashMap<ChunkCoord, SavedChunk>,
     pub dirty_chunks: HashMap<ChunkCoord, SavedChunk>,
     pub loaded_player_translation: Vec3,
@@ -52,6 +60,8 @@ impl Default for SaveState {
                 WorldMetadata {
                     version: SAVE_VERSION,
                     seed: DEFAULT_WORLD_SEED,
+                    preset: WorldPreset::default(),
+                    terrain: TerrainSettings::default(),
                     player_translation: Vec3::ZERO.to_array(),
                     inventory: Inventory::default().entries(),
                 },
@@ -61,14 +71,16 @@ impl Default for SaveState {
 
         let mut inventory = Inventory::default();
         inventory.clear();
-        for (voxel_type, count) in &metadata.inventory {
-            inventory.add(*voxel_type, *count);
+        for (block, count) in &metadata.inventory {
+            inventory.add(*block, *count);
         }
 
         Self {
             root,
             version: metadata.version,
             seed: metadata.seed,
+            preset: metadata.preset,
+            terrain: metadata.terrain,
             edited_chunks,
             dirty_chunks: HashMap::default(),
             loaded_player_translation: Vec3::from_array(metadata.player_translation),
@@ -92,13 +104,20 @@ impl SaveState {
         }
     }
 
-    pub fn start_new_world(&mut self) {
+    pub fn start_new_world(
+        &mut self,
+        blocks: &BlockRegistry,
+        preset: WorldPreset,
+        terrain: TerrainSettings,
+    ) {
         self.version = SAVE_VERSION;
         self.seed = fresh_world_seed();
+        self.preset = preset;
+        self.terrain = terrain;
         self.edited_chunks.clear();
         self.dirty_chunks.clear();
         self.loaded_player_translation = Vec3::ZERO;
-        self.loaded_inventory = Inventory::default();
+        self.loaded_inventory = Inventory::starting(blocks);
         self.dirty = false;
         self.pending_write = None;
     }
@@ -110,12 +129,14 @@ impl SaveState {
 
         let mut inventory = Inventory::default();
         inventory.clear();
-        for (voxel_type, count) in metadata.inventory {
-            inventory.add(voxel_type, count);
+        for (block, count) in metadata.inventory {
+            inventory.add(block, count);
         }
 
         self.version = metadata.version;
         self.seed = metadata.seed;
+        self.preset = metadata.preset;
+        self.terrain = metadata.terrain;
         self.edited_chunks = edited_chunks;
         self.dirty_chunks.clear();
         self.loaded_player_translation = Vec3::from_array(metadata.player_translation);
@@ -129,7 +150,7 @@ impl SaveState {
         if chunk.modified {
             let snapshot = SavedChunk {
                 coord: chunk.coord,
-                voxels: chunk.voxels.iter().map(|voxel| voxel.voxel_type).collect(),
+                voxels: chunk.voxels().collect(),
             };
             self.edited_chunks.insert(chunk.coord, snapshot.clone());
             self.dirty_chunks.insert(chunk.coord, snapshot);
@@ -190,17 +211,19 @@ fn build_save_snapshot(
             dirty_chunks.retain(|saved| saved.coord != chunk.coord);
             dirty_chunks.push(SavedChunk {
                 coord: chunk.coord,
-                voxels: chunk.voxels.iter().map(|voxel| voxel.voxel_type).collect(),
+                voxels: chunk.voxels().collect(),
             });
         }
     }
 
-    dirty_chunks.sort_by_key(|chunk| (chunk.coord.x, chunk.coord.z));
+    dirty_chunks.sort_by_key(|chunk| (chunk.coord.x, chunk.coord.y, chunk.coord.z));
 
     (
         WorldMetadata {
             version: save_state.version,
             seed: save_state.seed,
+            preset: save_state.preset,
+            terrain: save_state.terrain,
             player_translation: player_translation.to_array(),
             inventory: inventory.entries(),
         },
@@ -260,7 +283,7 @@ fn write_world_directory(
 }
 
 fn chunk_file_path(chunks_dir: &Path, coord: ChunkCoord) -> PathBuf {
-    chunks_dir.join(format!("chunk_{}_{}.bin", coord.x, coord.z))
+    chunks_dir.join(format!("chunk_{}_{}_{}.bin", coord.x, coord.y, coord.z))
 }
 
 fn write_world_metadata(path: &Path, metadata: &WorldMetadata) -> Result<(), String> {
@@ -268,6 +291,14 @@ fn write_world_metadata(path: &Path, metadata: &WorldMetadata) -> Result<(), Str
     bytes.extend_from_slice(SAVE_MAGIC);
     bytes.extend_from_slice(&metadata.version.to_le_bytes());
     bytes.extend_from_slice(&metadata.seed.to_le_bytes());
+    bytes.push(metadata.preset.id());
+    let terrain = serde_json::to_vec(&metadata.terrain).map_err(|error| error.to_string())?;
+    let terrain_len: u32 = terrain
+        .len()
+        .try_into()
+        .map_err(|_| "Terrain settings are too large to save".to_string())?;
+    bytes.extend_from_slice(&terrain_len.to_le_bytes());
+    bytes.extend_from_slice(&terrain);
     for value in metadata.player_translation {
         bytes.extend_from_slice(&value.to_le_bytes());
     }
@@ -277,8 +308,8 @@ fn write_world_metadata(path: &Path, metadata: &WorldMetadata) -> Result<(), Str
         .try_into()
         .map_err(|_| "Inventory is too large to save".to_string())?;
     bytes.extend_from_slice(&inventory_len.to_le_bytes());
-    for (voxel_type, count) in &metadata.inventory {
-        bytes.push(voxel_type_to_u8(*voxel_type));
+    for (block, count) in &metadata.inventory {
+        bytes.extend_from_slice(&block.0.to_le_bytes());
         bytes.extend_from_slice(&count.to_le_bytes());
     }
     fs::write(path, bytes).map_err(|error| error.to_string())
@@ -302,6 +333,16 @@ fn read_world_metadata(path: &Path) -> Result<WorldMetadata, String> {
     }
 
     let seed = read_u32(&mut cursor)?;
+    let preset_id = read_u8(&mut cursor)?;
+    let preset = WorldPreset::from_id(preset_id)
+        .ok_or_else(|| format!("Unknown world preset: {preset_id}"))?;
+    let terrain_len = read_u32(&mut cursor)? as usize;
+    let mut terrain = vec![0u8; terrain_len];
+    cursor
+        .read_exact(&mut terrain)
+        .map_err(|error| error.to_string())?;
+    let terrain = serde_json::from_slice(&terrain)
+        .map_err(|error| format!("Invalid terrain settings: {error}"))?;
     let mut player_translation = [0.0; 3];
     for value in &mut player_translation {
         *value = read_f32(&mut cursor)?;
@@ -310,23 +351,26 @@ fn read_world_metadata(path: &Path) -> Result<WorldMetadata, String> {
     let inventory_len = read_u32(&mut cursor)? as usize;
     let mut inventory = Vec::with_capacity(inventory_len);
     for _ in 0..inventory_len {
-        let voxel_type = voxel_type_from_u8(read_u8(&mut cursor)?)?;
+        let block = BlockId(read_u16(&mut cursor)?);
         let count = read_u32(&mut cursor)?;
-        inventory.push((voxel_type, count));
+        inventory.push((block, count));
     }
 
     Ok(WorldMetadata {
         version,
         seed,
+        preset,
+        terrain,
         player_translation,
         inventory,
     })
 }
 
 fn write_chunk_file(path: &Path, chunk: &SavedChunk) -> Result<(), String> {
-    let mut bytes = Vec::with_capacity(32 + chunk.voxels.len());
+    let mut bytes = Vec::with_capacity(32 + chunk.voxels.len() * 4);
     bytes.extend_from_slice(CHUNK_MAGIC);
     bytes.extend_from_slice(&chunk.coord.x.to_le_bytes());
+    bytes.extend_from_slice(&chunk.coord.y.to_le_bytes());
     bytes.extend_from_slice(&chunk.coord.z.to_le_bytes());
     let voxel_count: u32 = chunk
         .voxels
@@ -334,7 +378,10 @@ fn write_chunk_file(path: &Path, chunk: &SavedChunk) -> Result<(), String> {
         .try_into()
         .map_err(|_| "Chunk voxel count exceeds u32".to_string())?;
     bytes.extend_from_slice(&voxel_count.to_le_bytes());
-    bytes.extend(chunk.voxels.iter().map(|voxel| voxel_type_to_u8(*voxel)));
+    for voxel in &chunk.voxels {
+        bytes.extend_from_slice(&voxel.block.0.to_le_bytes());
+        bytes.extend_from_slice(&voxel.state.to_le_bytes());
+    }
     fs::write(path, bytes).map_err(|error| error.to_string())
 }
 
@@ -351,38 +398,22 @@ fn read_chunk_file(path: &Path) -> Result<SavedChunk, String> {
     }
 
     let x = read_i32(&mut cursor)?;
+    let y = read_i32(&mut cursor)?;
     let z = read_i32(&mut cursor)?;
     let voxel_count = read_u32(&mut cursor)? as usize;
     let mut voxels = Vec::with_capacity(voxel_count);
     for _ in 0..voxel_count {
-        voxels.push(voxel_type_from_u8(read_u8(&mut cursor)?)?);
+        let block = BlockId(read_u16(&mut cursor)?);
+        let state = read_u16(&mut cursor)?;
+        voxels.push(Voxel::with_state(block, state));
     }
 
     Ok(SavedChunk {
-        coord: ChunkCoord::new(x, z),
+        coord: ChunkCoord::new(x, y, z),
         voxels,
     })
 }
 
-fn voxel_type_to_u8(voxel_type: VoxelType) -> u8 {
-    match voxel_type {
-        VoxelType::Air => 0,
-        VoxelType::Stone => 1,
-        VoxelType::Dirt => 2,
-        VoxelType::Grass => 3,
-    }
-}
-
-fn voxel_type_from_u8(value: u8) -> Result<VoxelType, String> {
-    match value {
-        0 => Ok(VoxelType::Air),
-        1 => Ok(VoxelType::Stone),
-        2 => Ok(VoxelType::Dirt),
-        3 => Ok(VoxelType::Grass),
-        _ => Err(format!("Unknown voxel type id: {value}")),
-    }
-}
-
 fn read_u8(cursor: &mut Cursor<Vec<u8>>) -> Result<u8, String> {
     let mut bytes = [0u8; 1];
     cursor
@@ -391,6 +422,14 @@ fn read_u8(cursor: &mut Cursor<Vec<u8>>) -> Result<u8, String> {
     Ok(bytes[0])
 }
 
+fn read_u16(cursor: &mut Cursor<Vec<u8>>) -> Result<u16, String> {
+    let mut bytes = [0u8; 2];
+    cursor
+        .read_exact(&mut bytes)
+        .map_err(|error| error.to_string())?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
 fn read_u32(cursor: &mut Cursor<Vec<u8>>) -> Result<u32, String> {
     let mut bytes = [0u8; 4];
     cursor
@@ -421,3 +460,53 @@ fn fresh_world_seed() -> u32 {
         .map(|duration| duration.as_secs() as u32 ^ duration.subsec_nanos())
         .unwrap_or(DEFAULT_WORLD_SEED)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_metadata_round_trips_terrain_settings() {
+        let mut terrain = TerrainSettings::default();
+        terrain.water.sea_level_meters = 5.25;
+        terrain.caves.worm_chance = 0.1;
+        terrain.biomes.plains.base_height_meters = 6.1;
+        terrain.biomes.tundra.subsurface_depth_meters = 0.9;
+        terrain.max_height_meters = 8.5;
+        terrain.ores.gold.vein_size = 9;
+        terrain.decorations.desert.ruin = 0.2;
+        let metadata = WorldMetadata {
+            version: SAVE_VERSION,
+            seed: 99,
+            preset: WorldPreset::Noise,
+            terrain,
+            player_translation: [1.0, 2.0, 3.0],
+            inventory: vec![(BlockId(3), 12)],
+        };
+
+        let path = std::env::temp_dir().join(format!("gecynd-meta-{}.meta", std::process::id()));
+        write_world_metadata(&path, &metadata).unwrap();
+        let loaded = read_world_metadata(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.seed, 99);
+        assert_eq!(loaded.terrain, terrain);
+        assert_eq!(loaded.inventory, metadata.inventory);
+    }
+
+    #[test]
+    fn partial_terrain_settings_keep_each_biomes_defaults() {
+        let terrain: TerrainSettings =
+            serde_json::from_str(r#"{ "biomes": { "tundra": { "base_height_meters": 6.5 } } }"#)
+                .unwrap();
+
+        let defaults = TerrainSettings::default().biomes;
+        assert_eq!(terrain.biomes.tundra.base_height_meters, 6.5);
+        assert_eq!(
+            terrain.biomes.tundra.subsurface_depth_meters,
+            defaults.tundra.subsurface_depth_meters
+        );
+        assert_eq!(terrain.biomes.plains, defaults.plains);
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::block::BlockRegistry;
+use crate::generator::{ChunkGenerator, column_tops};
+use crate::player::PLAYER_HALF_EXTENTS;
+use crate::voxel::{BlockId, VOXEL_SIZE};
+use crate::world::{CHUNK_VOXELS_SIZE, voxel_cell};
+
+/// How far from the starting column the search looks before giving up, in meters.
+const SPAWN_SEARCH_RADIUS_METERS: f32 = 16.0;
+/// Largest height difference allowed across the ground under the player, in meters.
+const SPAWN_MAX_UNEVENNESS_METERS: f32 = 0.25;
+/// How far around a spawn point to look for walls that would trap the player, in meters.
+const SPAWN_ESCAPE_DISTANCE_METERS: f32 = 1.5;
+
+/// Finds where a new player can stand on the ground a world's generator produces, starting at
+/// the column under `origin` and spiraling outward. A spot qualifies when the whole collider
+/// footprint rests on roughly level, solid, opaque blocks with open sky above, so liquids, tree
+/// tops and overhangs are skipped, and when it is not walled in on every side, as at the bottom
+/// of a pit or cave mouth. Returns the player's translation, or `None` if nothing within reach
+/// qualifies.
+pub fn find_spawn_position(
+    generator: &dyn ChunkGenerator,
+    registry: &BlockRegistry,
+    origin: Vec3,
+) -> Option<Vec3> {
+    let footprint = (PLAYER_HALF_EXTENTS.x * 2.0 / VOXEL_SIZE).ceil() as i32;
+    let rings = (SPAWN_SEARCH_RADIUS_METERS / (footprint as f32 * VOXEL_SIZE)).ceil() as i32;
+    let origin_cell = voxel_cell(origin);
+    let center = IVec2::new(origin_cell.x, origin_cell.z);
+
+    let mut search = SpawnSearch {
+        generator,
+        registry,
+        footprint,
+        tiles: HashMap::default(),
+    };
+    spiral(rings).find_map(|offset| search.spawn_at(center + offset * footprint))
+}
+
+/// Offsets on square rings of growing size around the origin, the origin first.
+fn spiral(rings: i32) -> impl Iterator<Item = IVec2> {
+    (0..=rings).flat_map(|ring| {
+        (-ring..=ring)
+            .flat_map(move |x| (-ring..=ring).map(move |z| IVec2::new(x, z)))
+            .filter(move |offset| offset.x.abs().max(offset.y.abs()) == ring)
+    })
+}
+
+struct SpawnSearch<'a> {
+    generator: &'a dyn ChunkGenerator,
+    registry: &'a BlockRegistry,
+    /// Side of the player's collider, in voxels.
+    footprint: i32,
+    /// Column tops of every chunk column looked at so far.
+    tiles: HashMap<IVec2, Vec<Option<(i32, BlockId)>>>,
+}
+
+impl SpawnSearch<'_> {
+    /// Player translation for a collider centered on the corner between the four voxel columns
+    /// around `center`, if that spot qualifies.
+    fn spawn_at(&mut self, center: IVec2) -> Option<Vec3> {
+        let half = self.footprint / 2;
+        let (mut lowest, mut highest) = (i32::MAX, i32::MIN);
+        for x in center.x - half..center.x - half + self.footprint {
+            for z in center.y - half..center.y - half + self.footprint {
+                let (y, block) = self.top(IVec2::new(x, z))?;
+                if !self.registry.is_solid(block)
+                    || !self.registry.is_opaque(block)
+                    || self.registry.fluid(block).is_some()
+                {
+                    return None;
+                }
+                lowest = lowest.min(y);
+                highest = highest.max(y);
+            }
+        }
+        if (highest - lowest) as f32 * VOXEL_SIZE > SPAWN_MAX_UNEVENNESS_METERS
+            || self.is_walled_in(center, highest)
+        {
+            return None;
+        }
+
+        Some(Vec3::new(
+            center.x as f32 * VOXEL_SIZE,
+            (highest + 1) as f32 * VOXEL_SIZE + PLAYER_HALF_EXTENTS.y,
+            center.y as f32 * VOXEL_SIZE,
+        ))
+    }
+
+    /// Whether ground at least a player's height above `ground` rises within the escape
+    /// distance in all four directions.
+    fn is_walled_in(&mut self, center: IVec2, ground: i32) -> bool {
+        let half = self.footprint / 2;
+        let reach = (SPAWN_ESCAPE_DISTANCE_METERS / VOXEL_SIZE).round() as i32;
+        let wall = ground + (PLAYER_HALF_EXTENTS.y * 2.0 / VOXEL_SIZE).round() as i32;
+        [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .into_iter()
+            .all(|direction| {
+                (half..half + reach).any(|step| {
+                    self.top(center + direction * step)
+                        .is_some_and(|(y, _)| y >= wall)
+                })
+            })
+    }
+
+    fn top(&mut self, column: IVec2) -> Option<(i32, BlockId)> {
+        let size = IVec2::splat(CHUNK_VOXELS_SIZE as i32);
+        let chunk = column.div_euclid(size);
+        let local = column.rem_euclid(size);
+        let generator = self.generator;
+        let tile = self
+            .tiles
+            .entry(chunk)
+            .or_insert_with(|| column_tops(generator, chunk.x, chunk.y));
+        tile[(local.x + local.y * size.x) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::WorldPreset;
+    use crate::terrain::TerrainSettings;
+    use crate::voxel::Voxel;
+    use crate::world::Chunk;
+
+    /// Stone ground with its top at `GROUND_Y`, a lake west of x = 0 and a pit two meters deep
+    /// around the origin.
+    struct PitAndLake {
+        stone: BlockId,
+        water: BlockId,
+    }
+
+    const GROUND_Y: i32 = 64;
+    const PIT_SIZE: i32 = 24;
+
+    impl ChunkGenerator for PitAndLake {
+        fn generate_chunk(&self, chunk: &mut Chunk) {
+            let origin = chunk.coord.origin_cell();
+            for x in 0..CHUNK_VOXELS_SIZE {
+                for y in 0..CHUNK_VOXELS_SIZE {
+                    for z in 0..CHUNK_VOXELS_SIZE {
+                        let cell = origin + IVec3::new(x as i32, y as i32, z as i32);
+                        let in_pit =
+                            (0..PIT_SIZE).contains(&cell.x) && (0..PIT_SIZE).contains(&cell.z);
+                        let block = if in_pit && cell.y > GROUND_Y - 32 {
+                            continue;
+                        } else if cell.x < 0 && cell.y == GROUND_Y {
+                            self.water
+                        } else if cell.y <= GROUND_Y {
+                            self.stone
+                        } else {
+                            continue;
+                        };
+                        chunk.set_voxel(x, y, z, Voxel::new(block));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spawn_search_finds_open_dry_ground() {
+        let registry = BlockRegistry::default();
+        let generator = PitAndLake {
+            stone: registry.id_by_name("stone").unwrap(),
+            water: registry.id_by_name("water").unwrap(),
+        };
+        let origin = Vec3::new(0.5, 20.0, 0.5);
+        let spawn = find_spawn_position(&generator, &registry, origin).unwrap();
+
+        let feet = spawn.y - PLAYER_HALF_EXTENTS.y;
+        assert_eq!(feet, (GROUND_Y + 1) as f32 * VOXEL_SIZE);
+        let min = voxel_cell(spawn - PLAYER_HALF_EXTENTS);
+        let max = voxel_cell(spawn + PLAYER_HALF_EXTENTS - Vec3::splat(0.001));
+        let in_pit = min.x < PIT_SIZE && max.x >= 0 && min.z < PIT_SIZE && max.z >= 0;
+        assert!(!in_pit, "{spawn} is in the pit");
+        assert!(min.x >= 0, "{spawn} is in the lake");
+        assert!(
+            spawn.distance(origin.with_y(spawn.y)) < 1.5,
+            "{spawn} is needlessly far away"
+        );
+
+        for seed in [0, 12345] {
+            let terrain = WorldPreset::Noise.generator(seed, TerrainSettings::default(), &registry);
+            let spawn = find_spawn_position(&*terrain, &registry, origin).unwrap();
+            let under_feet = voxel_cell(spawn - Vec3::Y * (PLAYER_HALF_EXTENTS.y + 0.01));
+            let size = CHUNK_VOXELS_SIZE as i32;
+            let tops = column_tops(
+                &*terrain,
+                under_feet.x.div_euclid(size),
+                under_feet.z.div_euclid(size),
+            );
+            let local = under_feet.rem_euclid(IVec3::splat(size));
+            let (top, block) = tops[(local.x + local.z * size) as usize].unwrap();
+            let unevenness = (SPAWN_MAX_UNEVENNESS_METERS / VOXEL_SIZE) as i32;
+            assert!(
+                (0..=unevenness).contains(&(under_feet.y - top)),
+                "seed {seed}: {spawn} is not on the ground"
+            );
+            assert!(registry.is_solid(block) && registry.fluid(block).is_none());
+        }
+    }
+}
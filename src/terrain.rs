@@ -1,100 +1,795 @@
-use crate::voxel::{VOXEL_SIZE, Voxel, VoxelType};
-use crate::world::{CHUNK_VOXELS_HEIGHT, CHUNK_VOXELS_SIZE, Chunk, chunk_world_origin};
+use std::cell::OnceCell;
+use std::fs;
+use std::hash::Hash;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::biome::{Biome, BiomeMap, BiomeSettings, dominant_biome};
+use crate::block::BlockRegistry;
+use crate::cave::{CaveSettings, Caves, Tunnel};
+use crate::decoration::{DecorationSettings, Decorations};
+use crate::ore::{OreSettings, OreVeins};
+use crate::voxel::{BlockId, VOXEL_SIZE, Voxel};
+use crate::world::{CHUNK_VOXELS_SIZE, Chunk, ChunkCoord, chunk_world_origin};
 use noise::{NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+/// Chunk columns whose column shapes stay cached for the chunks stacked in them.
+const MAX_CACHED_COLUMN_TILES: usize = 1024;
+/// Columns whose surface height stays cached for decorations and cave entrances.
+const MAX_CACHED_SURFACES: usize = 16384;
+
+/// Everything that shapes a noise world besides its seed. A world keeps the settings it was
+/// created with in its metadata, so chunks generated by later builds with different defaults
+/// still line up with the ones already saved.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainSettings {
+    /// Lowest and highest the terrain surface reaches, in meters. Chunk loading, sky light and
+    /// the spawn search read them through `ChunkGenerator::surface_bounds`.
+    pub min_height_meters: f32,
+    pub max_height_meters: f32,
+    /// Horizontal frequencies of the three height noise octaves, per meter.
+    pub broad_frequency: f64,
+    pub rolling_frequency: f64,
+    pub detail_frequency: f64,
+    /// Horizontal frequency of the noise that caps columns with bare stone, per meter.
+    pub stone_cap_frequency: f64,
+    /// Noise value above which a column's surface is bare stone.
+    pub stone_cap_threshold: f64,
+    pub biomes: BiomeSettings,
+    pub density: DensitySettings,
+    pub water: WaterSettings,
+    pub caves: CaveSettings,
+    pub ores: OreSettings,
+    pub decorations: DecorationSettings,
+}
+
+impl TerrainSettings {
+    /// Reads settings from a JSON file in the format saved in world metadata. Missing fields keep
+    /// their defaults.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+        serde_json::from_str(&source).map_err(|error| format!("Invalid terrain settings: {error}"))
+    }
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        Self {
+            min_height_meters: 3.5,
+            max_height_meters: 9.5,
+            broad_frequency: 0.010,
+            rolling_frequency: 0.026,
+            detail_frequency: 0.060,
+            stone_cap_frequency: 0.045,
+            stone_cap_threshold: 0.58,
+            biomes: BiomeSettings::default(),
+            density: DensitySettings::default(),
+            water: WaterSettings::default(),
+            caves: CaveSettings::default(),
+            ores: OreSettings::default(),
+            decorations: DecorationSettings::default(),
+        }
+    }
+}
+
+/// Tunables of the density function that decides solid and air per voxel. The heightmap gives
+/// the rough shape, and 3D noise pushes the surface in and out where the cliff mask allows it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DensitySettings {
+    /// Horizontal frequency of the mask that decides where cliffs form, per meter.
+    pub cliff_frequency: f64,
+    /// Mask value above which terrain starts breaking into cliffs; lower values give more cliffs.
+    pub cliff_threshold: f32,
+    /// Farthest the 3D noise moves the surface on a full cliff, in meters.
+    pub overhang_amplitude_meters: f32,
+    /// Horizontal frequency of the 3D noise that carves cliffs and overhangs, per meter.
+    pub overhang_frequency: f64,
+    /// Vertical frequency relative to the horizontal one. Values above one break the surface
+    /// into ledges and overhangs, values below one stretch it into tall, steep walls.
+    pub vertical_squash: f64,
+}
+
+impl Default for DensitySettings {
+    fn default() -> Self {
+        Self {
+            cliff_frequency: 0.035,
+            cliff_threshold: 0.15,
+            overhang_amplitude_meters: 2.2,
+            overhang_frequency: 0.5,
+            vertical_squash: 1.5,
+        }
+    }
+}
+
+/// Tunables of the water level and the valleys and basins carved down to it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WaterSettings {
+    /// Open air below this height is filled with water, in meters.
+    pub sea_level_meters: f32,
+    /// How far above sea level shorelines turn to sand, in meters.
+    pub beach_height_meters: f32,
+    /// Horizontal frequency of the noise whose zero crossings rivers follow, per meter.
+    pub river_frequency: f64,
+    /// Half width of a river channel, in units of river noise.
+    pub river_width: f32,
+    /// Depth of a river bed below sea level, in meters.
+    pub river_depth_meters: f32,
+    /// Horizontal frequency of the noise that places lake basins, per meter.
+    pub lake_frequency: f64,
+    /// Lake noise value above which basins form; higher values give fewer, smaller lakes.
+    pub lake_threshold: f32,
+    /// Depth of the middle of a lake basin below sea level, in meters.
+    pub lake_depth_meters: f32,
+}
+
+impl Default for WaterSettings {
+    fn default() -> Self {
+        Self {
+            sea_level_meters: 4.6,
+            beach_height_meters: 0.3,
+            river_frequency: 0.012,
+            river_width: 0.04,
+            river_depth_meters: 0.5,
+            lake_frequency: 0.02,
+            lake_threshold: 0.4,
+            lake_depth_meters: 0.9,
+        }
+    }
+}
+
+/// Per-column inputs of the density function.
+#[derive(Clone, Copy, Debug)]
+struct ColumnShape {
+    height: f32,
+    /// How strongly the 3D noise applies to the column, from 0 (plain heightmap) to 1.
+    cliff: f32,
+    biome: Biome,
+}
+
+/// Column shapes of every column of a chunk column, indexed `x + z * CHUNK_VOXELS_SIZE`.
+struct ColumnTile {
+    shapes: Vec<ColumnShape>,
+}
+
+/// Memo of pure per-column results, shared by generation tasks on every thread. It forgets
+/// everything once full, which keeps it bounded without tracking use. The lock is not held
+/// while computing, so two threads may now and then compute the same entry.
+struct ColumnCache<K, V> {
+    capacity: usize,
+    entries: Mutex<HashMap<K, V>>,
+}
+
+impl<K: Eq + Hash, V: Clone> ColumnCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(HashMap::default()),
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.lock().get(key).cloned()
+    }
 
-pub const TERRAIN_MIN_HEIGHT_METERS: f32 = 3.5;
-pub const TERRAIN_MAX_HEIGHT_METERS: f32 = 9.5;
-const TERRAIN_BASE_HEIGHT_METERS: f32 = 5.8;
-const TERRAIN_PRIMARY_VARIATION_METERS: f32 = 2.1;
-const TERRAIN_SECONDARY_VARIATION_METERS: f32 = 0.9;
-const TERRAIN_DETAIL_VARIATION_METERS: f32 = 0.35;
-const DIRT_LAYER_THICKNESS_METERS: f32 = 0.7;
-const STONE_CAP_NOISE_THRESHOLD: f64 = 0.58;
+    fn get_or_insert_with(&self, key: K, compute: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = compute();
+        let mut entries = self.lock();
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+        entries.insert(key, value.clone());
+        value
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<K, V>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
 
 pub struct TerrainGenerator {
     broad_noise: Perlin,
     rolling_noise: Perlin,
     detail_noise: Perlin,
     surface_noise: Perlin,
-    cave_noise: Perlin,
-    ore_noise: Perlin,
+    cliff_noise: Perlin,
+    overhang_noise: Perlin,
+    river_noise: Perlin,
+    lake_noise: Perlin,
+    settings: TerrainSettings,
+    biomes: BiomeMap,
+    blocks: TerrainBlocks,
+    ores: OreVeins,
+    caves: Caves,
+    decorations: Decorations,
+    column_tiles: ColumnCache<(i32, i32), Arc<ColumnTile>>,
+    surfaces: ColumnCache<IVec2, (i32, Biome)>,
+}
+
+/// Block ids the generator places, resolved by name from the registry.
+struct TerrainBlocks {
+    stone: BlockId,
+    sand: BlockId,
+    water: BlockId,
+    /// Surface and subsurface block of every biome, in `Biome::ALL` order.
+    biome_layers: [(BlockId, BlockId); Biome::ALL.len()],
+}
+
+impl TerrainBlocks {
+    fn resolve(registry: &BlockRegistry) -> Self {
+        let id = |name: &str| registry.generated_block(name);
+        Self {
+            stone: id("stone"),
+            sand: id("sand"),
+            water: id("water"),
+            biome_layers: Biome::ALL.map(|biome| {
+                let terrain = biome.terrain();
+                (id(terrain.surface), id(terrain.subsurface))
+            }),
+        }
+    }
+
+    fn layers(&self, biome: Biome) -> (BlockId, BlockId) {
+        let index = Biome::ALL.iter().position(|other| *other == biome);
+        self.biome_layers[index.unwrap_or_default()]
+    }
 }
 
 impl TerrainGenerator {
-    pub fn new(seed: u32) -> Self {
+    pub fn new(seed: u32, registry: &BlockRegistry, settings: TerrainSettings) -> Self {
         Self {
             broad_noise: Perlin::new(seed),
             rolling_noise: Perlin::new(seed.wrapping_add(67890)),
             detail_noise: Perlin::new(seed.wrapping_add(24680)),
             surface_noise: Perlin::new(seed.wrapping_add(13579)),
-            cave_noise: Perlin::new(seed.wrapping_add(424242)),
-            ore_noise: Perlin::new(seed.wrapping_add(919191)),
+            cliff_noise: Perlin::new(seed.wrapping_add(97531)),
+            overhang_noise: Perlin::new(seed.wrapping_add(86420)),
+            river_noise: Perlin::new(seed.wrapping_add(55555)),
+            lake_noise: Perlin::new(seed.wrapping_add(20202)),
+            settings,
+            biomes: BiomeMap::new(seed),
+            blocks: TerrainBlocks::resolve(registry),
+            ores: OreVeins::new(seed, registry, &settings.ores),
+            caves: Caves::new(seed, settings.caves),
+            decorations: Decorations::new(seed, registry, settings.decorations),
+            column_tiles: ColumnCache::new(MAX_CACHED_COLUMN_TILES),
+            surfaces: ColumnCache::new(MAX_CACHED_SURFACES),
         }
     }
 
     pub fn generate_chunk(&self, chunk: &mut Chunk) {
         let chunk_origin = chunk_world_origin(chunk.coord);
-        let dirt_voxels = (DIRT_LAYER_THICKNESS_METERS / VOXEL_SIZE).max(1.0).round() as i32;
+        if chunk_origin.y
+            > self.settings.max_height_meters + self.settings.decorations.max_height_meters()
+        {
+            return;
+        }
+
+        if chunk_origin.y <= self.settings.max_height_meters {
+            self.generate_terrain(chunk);
+            self.ores.place_in_chunk(chunk);
+        }
+        self.decorations.decorate_chunk(self, chunk);
+    }
+
+    pub fn settings(&self) -> &TerrainSettings {
+        &self.settings
+    }
+
+    /// Dominant biome at world `x`/`z` in meters.
+    pub fn biome_at(&self, x: f64, z: f64) -> Biome {
+        self.biomes.biome_at(x, z)
+    }
+
+    /// Topmost solid voxel and dominant biome of the column at global cell `x`/`z`. Every column
+    /// has one no higher than the maximum terrain height and no lower than the voxel just below
+    /// the minimum, whatever the density settings.
+    pub fn surface_column(&self, x: i32, z: i32) -> (i32, Biome) {
+        self.surfaces.get_or_insert_with(IVec2::new(x, z), || {
+            let shape = self.shape_at(x, z);
+            let top = (self.settings.max_height_meters / VOXEL_SIZE).floor() as i32;
+            let bottom = (self.settings.min_height_meters / VOXEL_SIZE).floor() as i32;
+            let surface_y = (bottom..=top)
+                .rev()
+                .find(|&y| self.is_solid(&shape, IVec3::new(x, y, z)))
+                .unwrap_or(bottom - 1);
+            (surface_y, shape.biome)
+        })
+    }
+
+    /// Column shapes of a chunk column, computed once for all the chunks stacked in it.
+    fn column_tile(&self, chunk_x: i32, chunk_z: i32) -> Arc<ColumnTile> {
+        self.column_tiles
+            .get_or_insert_with((chunk_x, chunk_z), || {
+                let origin = ChunkCoord::new(chunk_x, 0, chunk_z).origin_cell();
+                let size = CHUNK_VOXELS_SIZE as i32;
+                let shapes = (0..size)
+                    .flat_map(|z| (0..size).map(move |x| (x, z)))
+                    .map(|(x, z)| self.column_shape(origin.x + x, origin.z + z))
+                    .collect();
+                Arc::new(ColumnTile { shapes })
+            })
+    }
+
+    /// Shape of a single column, taken from its chunk column's tile when that is cached.
+    fn shape_at(&self, x: i32, z: i32) -> ColumnShape {
+        let size = CHUNK_VOXELS_SIZE as i32;
+        let (chunk_x, chunk_z) = (x.div_euclid(size), z.div_euclid(size));
+        match self.column_tiles.get(&(chunk_x, chunk_z)) {
+            Some(tile) => tile.shapes[(x.rem_euclid(size) + z.rem_euclid(size) * size) as usize],
+            None => self.column_shape(x, z),
+        }
+    }
+
+    fn column_shape(&self, x: i32, z: i32) -> ColumnShape {
+        let world_x = (x as f32 * VOXEL_SIZE) as f64;
+        let world_z = (z as f32 * VOXEL_SIZE) as f64;
+        let weights = self.biomes.weights(world_x, world_z);
+        let mask = self.cliff_noise.get([
+            world_x * self.settings.density.cliff_frequency,
+            world_z * self.settings.density.cliff_frequency,
+        ]) as f32;
+        let threshold = self.settings.density.cliff_threshold;
+
+        let cliff = ((mask - threshold) / (1.0 - threshold).max(f32::EPSILON)).clamp(0.0, 1.0);
+        let height = self.get_height(world_x, world_z, &weights) as f32;
+        let (height, carve) = self.carve_water(world_x, world_z, height);
+
+        ColumnShape {
+            height: height.clamp(
+                self.settings.min_height_meters,
+                self.settings.max_height_meters,
+            ),
+            // Keep banks smooth, so overhangs don't leave water hanging under them.
+            cliff: cliff * (1.0 - carve),
+            biome: dominant_biome(&weights),
+        }
+    }
+
+    /// Lowers a column toward its river bed or lake basin below sea level. Returns the new
+    /// height and how strongly the column was carved, from 0 (untouched) to 1 (on the bed).
+    fn carve_water(&self, x: f64, z: f64, height: f32) -> (f32, f32) {
+        let water = &self.settings.water;
+
+        // Rivers run along the zero crossings of the noise, with banks three times as wide as
+        // the channel sloping down into it.
+        let river = self
+            .river_noise
+            .get([x * water.river_frequency, z * water.river_frequency])
+            .abs() as f32;
+        let bank_width = water.river_width * 3.0;
+        let river_carve = smoothstep((bank_width - river) / (bank_width - water.river_width));
+
+        let lake = self
+            .lake_noise
+            .get([x * water.lake_frequency, z * water.lake_frequency]) as f32;
+        let lake_carve = smoothstep((lake - water.lake_threshold) / 0.2);
+
+        let mut carved = height;
+        for (carve, depth) in [
+            (river_carve, water.river_depth_meters),
+            (lake_carve, water.lake_depth_meters),
+        ] {
+            let bed = water.sea_level_meters - depth;
+            if bed < carved {
+                carved += (bed - carved) * carve;
+            }
+        }
+        (carved, river_carve.max(lake_carve))
+    }
+
+    /// Highest cell that is filled with water when open to the sky.
+    pub fn sea_level_cell(&self) -> i32 {
+        (self.settings.water.sea_level_meters / VOXEL_SIZE).ceil() as i32 - 1
+    }
+
+    /// Whether the density function fills a cell. Cells above the maximum terrain height are
+    /// always air and cells below the minimum always solid, so every column has ground to stand
+    /// on and open sky above.
+    fn is_solid(&self, shape: &ColumnShape, cell: IVec3) -> bool {
+        let world = cell.as_vec3() * VOXEL_SIZE;
+        if world.y > self.settings.max_height_meters {
+            return false;
+        }
+        if world.y < self.settings.min_height_meters {
+            return true;
+        }
+
+        // The noise stays within one reach of the heightmap, so only cells that close to the
+        // surface need it.
+        let density = shape.height - world.y;
+        let reach = self.overhang_reach(shape);
+        if density >= reach {
+            return true;
+        }
+        if density < -reach {
+            return false;
+        }
+
+        let frequency = self.settings.density.overhang_frequency;
+        let noise = self.overhang_noise.get([
+            world.x as f64 * frequency,
+            world.y as f64 * frequency * self.settings.density.vertical_squash,
+            world.z as f64 * frequency,
+        ]) as f32;
+        density + noise * reach >= 0.0
+    }
+
+    /// Farthest the 3D noise moves a column's surface from its heightmap, in meters.
+    fn overhang_reach(&self, shape: &ColumnShape) -> f32 {
+        shape.cliff * self.settings.density.overhang_amplitude_meters
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk) {
+        let chunk_origin = chunk_world_origin(chunk.coord);
+        let origin_cell = chunk.coord.origin_cell();
+        let top_y = origin_cell.y + CHUNK_VOXELS_SIZE as i32 - 1;
+        let max_y = (self.settings.max_height_meters / VOXEL_SIZE).floor() as i32;
+        let sea_level_y = self.sea_level_cell();
+        let beach_top =
+            self.settings.water.sea_level_meters + self.settings.water.beach_height_meters;
+        let tile = self.column_tile(chunk.coord.x, chunk.coord.z);
+        // Only chunks with rock in them need to know where the worm caves run.
+        let worms = OnceCell::new();
+        let roof_voxels = self.settings.caves.roof_voxels;
 
         for x in 0..CHUNK_VOXELS_SIZE {
             for z in 0..CHUNK_VOXELS_SIZE {
+                let cell_x = origin_cell.x + x as i32;
+                let cell_z = origin_cell.z + z as i32;
                 let world_x = chunk_origin.x + x as f32 * VOXEL_SIZE;
                 let world_z = chunk_origin.z + z as f32 * VOXEL_SIZE;
 
-                let height = self.get_height(world_x as f64, world_z as f64) as f32;
-                let surface_voxel_y = (height / VOXEL_SIZE).floor() as i32;
-                let surface_noise = self
-                    .surface_noise
-                    .get([world_x as f64 * 0.045, world_z as f64 * 0.045]);
-
-                for y in 0..CHUNK_VOXELS_HEIGHT {
-                    let yi = y as i32;
-                    let world_y = y as f32 * VOXEL_SIZE;
-                    let cave_noise = self.cave_noise.get([
-                        world_x as f64 * 0.055,
-                        world_y as f64 * 0.055,
-                        world_z as f64 * 0.055,
-                    ]);
-                    let ore_noise = self.ore_noise.get([
-                        world_x as f64 * 0.09,
-                        world_y as f64 * 0.09,
-                        world_z as f64 * 0.09,
-                    ]);
-
-                    let voxel_type = if yi > surface_voxel_y {
-                        VoxelType::Air
-                    } else if yi < surface_voxel_y - 6 && cave_noise > 0.42 {
-                        VoxelType::Air
-                    } else if yi == surface_voxel_y {
-                        if surface_noise > STONE_CAP_NOISE_THRESHOLD {
-                            VoxelType::Stone
+                let shape = tile.shapes[x + z * CHUNK_VOXELS_SIZE];
+                // A column that nothing reaches up into stays the air the chunk starts as.
+                if origin_cell.y > sea_level_y
+                    && chunk_origin.y > shape.height + self.overhang_reach(&shape)
+                {
+                    continue;
+                }
+                let solid = |y| self.is_solid(&shape, IVec3::new(cell_x, y, cell_z));
+                let subsurface_voxels = (self
+                    .settings
+                    .biomes
+                    .shape(shape.biome)
+                    .subsurface_depth_meters
+                    / VOXEL_SIZE)
+                    .max(1.0)
+                    .round() as i32;
+                let stone_cap_frequency = self.settings.stone_cap_frequency;
+                let surface_noise = self.surface_noise.get([
+                    world_x as f64 * stone_cap_frequency,
+                    world_z as f64 * stone_cap_frequency,
+                ]);
+
+                // Count the solid run just above the chunk, so layers and cave roofs continue
+                // across its top face.
+                let look_ahead = subsurface_voxels.max(roof_voxels) + 1;
+                let mut depth = 0;
+                let mut run_top = top_y;
+                for yi in (top_y + 1..=top_y + look_ahead).rev() {
+                    if !solid(yi) {
+                        depth = 0;
+                        continue;
+                    }
+                    if depth == 0 {
+                        run_top = yi;
+                    }
+                    depth += 1;
+                }
+                // Only air the sky reaches is flooded, so caves and pockets under overhangs
+                // stay dry.
+                let mut open_sky =
+                    origin_cell.y <= sea_level_y && (top_y + 1..=max_y).all(|y| !solid(y));
+
+                for y in (0..CHUNK_VOXELS_SIZE).rev() {
+                    let yi = origin_cell.y + y as i32;
+                    if !solid(yi) {
+                        depth = 0;
+                        let block = if open_sky && yi <= sea_level_y {
+                            self.blocks.water
                         } else {
-                            VoxelType::Grass
+                            BlockId::AIR
+                        };
+                        chunk.set_voxel(x, y, z, Voxel::new(block));
+                        continue;
+                    }
+                    open_sky = false;
+                    if depth == 0 {
+                        run_top = yi;
+                    }
+
+                    // Shores and the ground under water are sand whatever the biome.
+                    let beach = run_top as f32 * VOXEL_SIZE <= beach_top;
+                    let (surface_block, subsurface_block) = if beach {
+                        (self.blocks.sand, self.blocks.sand)
+                    } else {
+                        self.blocks.layers(shape.biome)
+                    };
+
+                    // Caves keep a roof over them, except where an entrance breaks through dry
+                    // ground.
+                    let roofed = depth > roof_voxels;
+                    let carved = match worms
+                        .get_or_init(|| self.caves.worm_mask(self, chunk.coord))
+                        .get(x, y, z)
+                    {
+                        Some(Tunnel::Entrance) => roofed || run_top > sea_level_y,
+                        Some(Tunnel::Underground) => roofed,
+                        None => roofed && self.caves.is_noodle(IVec3::new(cell_x, yi, cell_z)),
+                    };
+                    let block = if carved {
+                        BlockId::AIR
+                    } else if depth == 0 {
+                        if !beach && surface_noise > self.settings.stone_cap_threshold {
+                            self.blocks.stone
+                        } else {
+                            surface_block
                         }
-                    } else if yi >= surface_voxel_y - dirt_voxels {
-                        VoxelType::Dirt
-                    } else if yi < surface_voxel_y - dirt_voxels - 8 && ore_noise > 0.63 {
-                        VoxelType::Grass
+                    } else if depth <= subsurface_voxels {
+                        subsurface_block
                     } else {
-                        VoxelType::Stone
+                        self.blocks.stone
                     };
 
-                    chunk.set_voxel(x, y, z, Voxel::new(voxel_type));
+                    depth += 1;
+                    chunk.set_voxel(x, y, z, Voxel::new(block));
+                }
+            }
+        }
+    }
+
+    /// Surface height of a column, with each biome's shape parameters weighted by its influence.
+    fn get_height(&self, x: f64, z: f64, weights: &[f32; Biome::ALL.len()]) -> f64 {
+        let octave =
+            |noise: &Perlin, frequency: f64| noise.get([x * frequency, z * frequency]) as f32;
+        let broad = octave(&self.broad_noise, self.settings.broad_frequency);
+        let rolling = octave(&self.rolling_noise, self.settings.rolling_frequency);
+        let detail = octave(&self.detail_noise, self.settings.detail_frequency);
+
+        let height = Biome::ALL
+            .into_iter()
+            .zip(weights.iter().copied())
+            .map(|(biome, weight)| {
+                let shape = self.settings.biomes.shape(biome);
+                weight
+                    * (shape.base_height_meters
+                        + broad * shape.primary_variation_meters
+                        + rolling * shape.secondary_variation_meters
+                        + detail * shape.detail_variation_meters)
+            })
+            .sum::<f32>();
+
+        height.clamp(
+            self.settings.min_height_meters,
+            self.settings.max_height_meters,
+        ) as f64
+    }
+}
+
+/// Smooth step from 0 to 1 as `t` goes from 0 to 1, clamped outside that range.
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ChunkCoord, split_cell};
+
+    /// Checks that every sampled column has ground and returns how many have air under it.
+    fn count_overhangs(terrain: &TerrainGenerator) -> usize {
+        let min_y = (terrain.settings.min_height_meters / VOXEL_SIZE).floor() as i32 - 1;
+        let max_y = (terrain.settings.max_height_meters / VOXEL_SIZE).floor() as i32;
+
+        let mut overhangs = 0;
+        for x in (-600..600).step_by(24) {
+            for z in (-600..600).step_by(24) {
+                let (surface_y, _) = terrain.surface_column(x, z);
+                assert!((min_y..=max_y).contains(&surface_y));
+
+                let shape = terrain.column_shape(x, z);
+                let solid = |y| terrain.is_solid(&shape, IVec3::new(x, y, z));
+                assert!(solid(surface_y) && !solid(surface_y + 1));
+                if (min_y..surface_y).any(|y| !solid(y)) {
+                    overhangs += 1;
+                }
+            }
+        }
+        overhangs
+    }
+
+    #[test]
+    fn density_terrain_overhangs_but_every_column_has_ground() {
+        let registry = BlockRegistry::default();
+        let with_cliff_threshold = |cliff_threshold| {
+            let mut settings = TerrainSettings::default();
+            settings.density.cliff_threshold = cliff_threshold;
+            TerrainGenerator::new(5, &registry, settings)
+        };
+
+        let cliffs = with_cliff_threshold(-0.2);
+        assert!(count_overhangs(&cliffs) > 0);
+        assert_eq!(count_overhangs(&with_cliff_threshold(1.0)), 0);
+
+        // The spawn column still generates ground at the surface it reports.
+        let (surface_y, _) = cliffs.surface_column(0, 0);
+        let (coord, x, y, z) = split_cell(IVec3::new(0, surface_y, 0));
+        let mut chunk = Chunk::new(coord);
+        cliffs.generate_chunk(&mut chunk);
+        assert!(!chunk.get_voxel(x, y, z).unwrap().block.is_air());
+    }
+
+    fn block_at(terrain: &TerrainGenerator, cell: IVec3) -> BlockId {
+        let (coord, x, y, z) = split_cell(cell);
+        let mut chunk = Chunk::new(coord);
+        terrain.generate_chunk(&mut chunk);
+        chunk.get_voxel(x, y, z).unwrap().block
+    }
+
+    #[test]
+    fn rivers_and_lakes_fill_to_sea_level_with_sandy_shores() {
+        let registry = BlockRegistry::default();
+        let id = |name| registry.id_by_name(name).unwrap();
+        let terrain = TerrainGenerator::new(3, &registry, TerrainSettings::default());
+        let water = WaterSettings::default();
+        let sea_level_y = terrain.sea_level_cell();
+
+        let (mut flooded, mut shores, mut rivers) = (0, 0, 0);
+        for x in (-4000..4000).step_by(40) {
+            for z in (-4000..4000).step_by(40) {
+                let (surface_y, _) = terrain.surface_column(x, z);
+                if surface_y < sea_level_y && flooded < 4 {
+                    flooded += 1;
+                    assert_eq!(
+                        block_at(&terrain, IVec3::new(x, sea_level_y, z)),
+                        id("water")
+                    );
+                    assert_eq!(block_at(&terrain, IVec3::new(x, surface_y, z)), id("sand"));
+                    assert!(block_at(&terrain, IVec3::new(x, sea_level_y + 1, z)).is_air());
+                } else if surface_y == sea_level_y + 1 && shores < 4 {
+                    shores += 1;
+                    assert_eq!(block_at(&terrain, IVec3::new(x, surface_y, z)), id("sand"));
+                }
+
+                // Rivers cut below sea level even where the land around them is high.
+                let world_x = (x as f32 * VOXEL_SIZE) as f64;
+                let world_z = (z as f32 * VOXEL_SIZE) as f64;
+                let weights = terrain.biomes.weights(world_x, world_z);
+                let land = terrain.get_height(world_x, world_z, &weights) as f32;
+                let river = terrain.river_noise.get([
+                    world_x * water.river_frequency,
+                    world_z * water.river_frequency,
+                ]);
+                if land > water.sea_level_meters + 1.0 && river.abs() < 0.01 {
+                    rivers += 1;
+                    assert!(surface_y < sea_level_y);
                 }
             }
         }
+        assert!(flooded > 0 && shores > 0 && rivers > 0);
+    }
+
+    /// A shoreline at sea level, surface above caves, caves further down and deep chunks on both
+    /// sides of every axis origin.
+    const GOLDEN_COORDS: [(i32, i32, i32); 6] = [
+        (-2, 2, -1),
+        (3, 3, -1),
+        (2, 1, 4),
+        (4, 2, -7),
+        (0, -3, 0),
+        (-9, -6, -2),
+    ];
+
+    fn chunk_hash(terrain: &TerrainGenerator, (x, y, z): (i32, i32, i32)) -> u64 {
+        let mut chunk = Chunk::new(ChunkCoord::new(x, y, z));
+        terrain.generate_chunk(&mut chunk);
+        chunk.content_hash()
+    }
+
+    #[test]
+    fn golden_chunk_hashes() {
+        // Regenerate these only for a deliberate change to generation, since saved edits are
+        // laid over freshly generated chunks.
+        let golden: [(u32, [u64; GOLDEN_COORDS.len()]); 3] = [
+            (
+                0,
+                [
+                    0x5f1b_b0b3_fd9e_b44c,
+                    0x682c_be00_8a5e_31c3,
+                    0x7da3_5bce_3e6c_5f37,
+                    0x7747_4478_faf1_7308,
+                    0x58c1_9eee_3f3b_a0aa,
+                    0x9cca_6077_cb30_2d95,
+                ],
+            ),
+            (
+                12345,
+                [
+                    0xb48b_d02e_2829_880b,
+                    0x7f00_b18a_21d1_d2f5,
+                    0xeb05_5760_7768_7bf6,
+                    0x91eb_f82a_0423_65e8,
+                    0xd5bd_de4d_678a_ae5a,
+                    0xd1fb_ad18_8841_58a4,
+                ],
+            ),
+            (
+                u32::MAX,
+                [
+                    0x460d_3662_770b_8e1b,
+                    0xf133_e119_da33_7ead,
+                    0x756d_6221_848b_80e5,
+                    0xf354_4613_ece8_5b3b,
+                    0x915e_43c2_92cb_2bf5,
+                    0x81bb_8afc_0343_5284,
+                ],
+            ),
+        ];
+
+        let registry = BlockRegistry::default();
+        for (seed, hashes) in golden {
+            // Repeated hashes would mean featureless chunks that pin down nothing.
+            let distinct = hashes.iter().collect::<std::collections::HashSet<_>>();
+            assert_eq!(
+                distinct.len(),
+                hashes.len(),
+                "seed {seed} repeats a golden hash"
+            );
+
+            let terrain = TerrainGenerator::new(seed, &registry, TerrainSettings::default());
+            for (coord, hash) in GOLDEN_COORDS.into_iter().zip(hashes) {
+                assert_eq!(
+                    chunk_hash(&terrain, coord),
+                    hash,
+                    "seed {seed}, chunk {coord:?}"
+                );
+            }
+        }
     }
 
-    fn get_height(&self, x: f64, z: f64) -> f64 {
-        let broad = self.broad_noise.get([x * 0.010, z * 0.010]) as f32;
-        let rolling = self.rolling_noise.get([x * 0.026, z * 0.026]) as f32;
-        let detail = self.detail_noise.get([x * 0.060, z * 0.060]) as f32;
+    #[test]
+    fn generation_order_and_thread_do_not_change_chunks() {
+        let registry = BlockRegistry::default();
+        let terrain = TerrainGenerator::new(77, &registry, TerrainSettings::default());
+        let coords = (-2..=1)
+            .flat_map(|x| (0..=2).flat_map(move |y| (-1..=1).map(move |z| (x, y, z))))
+            .collect::<Vec<_>>();
+
+        let forward = coords
+            .iter()
+            .map(|coord| chunk_hash(&terrain, *coord))
+            .collect::<Vec<_>>();
 
-        let height = TERRAIN_BASE_HEIGHT_METERS
-            + broad * TERRAIN_PRIMARY_VARIATION_METERS
-            + rolling * TERRAIN_SECONDARY_VARIATION_METERS
-            + detail * TERRAIN_DETAIL_VARIATION_METERS;
+        let mut backward = coords
+            .iter()
+            .rev()
+            .map(|coord| chunk_hash(&terrain, *coord))
+            .collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(forward, backward);
 
-        height.clamp(TERRAIN_MIN_HEIGHT_METERS, TERRAIN_MAX_HEIGHT_METERS) as f64
+        let threaded = std::thread::scope(|scope| {
+            let handles = coords
+                .iter()
+                .map(|coord| scope.spawn(|| chunk_hash(&terrain, *coord)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(forward, threaded);
     }
 }
@@ -1,13 +1,14 @@
 use bevy::prelude::*;
 
 use crate::AppState;
-use crate::player::{
-    HOTBAR_MATERIALS, Inventory, Player, PlayerInteraction, selected_material_index,
-};
+use crate::block::BlockRegistry;
+use crate::generator::WorldPreset;
+use crate::player::{Inventory, Player, PlayerInteraction, selected_material_index};
 use crate::save::{SaveState, flush_pending_save, queue_manual_save};
-use crate::voxel::VoxelType;
-use crate::world::Chunk;
-use crate::world::{DebugInfoState, InitialWorldGeneration};
+use crate::terrain::TerrainSettings;
+use crate::voxel::BlockId;
+use crate::world::{Chunk, VoxelAccess, VoxelReader};
+use crate::world::{DebugInfoState, InitialWorldGeneration, ViewDistanceSettings};
 
 const NORMAL_BUTTON: Color = Color::srgb(0.30, 0.30, 0.30);
 const HOVERED_BUTTON: Color = Color::srgb(0.42, 0.42, 0.42);
@@ -22,7 +23,9 @@ pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_ui_camera)
+        app.init_resource::<NewWorldPreset>()
+            .init_resource::<NewWorldTerrain>()
+            .add_systems(Startup, setup_ui_camera)
             .add_systems(OnEnter(AppState::MainMenu), setup_main_menu)
             .add_systems(OnExit(AppState::MainMenu), cleanup_main_menu)
             .add_systems(
@@ -86,12 +89,12 @@ struct HotbarSlot {
 
 #[derive(Component)]
 struct HotbarSlotLabel {
-    material: VoxelType,
+    material: BlockId,
 }
 
 #[derive(Component)]
 struct HotbarSlotCount {
-    material: VoxelType,
+    material: BlockId,
 }
 
 #[derive(Component)]
@@ -117,8 +120,17 @@ struct PauseMenuButton {
 enum MainMenuAction {
     NewSave,
     LoadSave,
+    CyclePreset,
 }
 
+/// Generator preset the next "New Save" creates its world with.
+#[derive(Resource, Default)]
+struct NewWorldPreset(WorldPreset);
+
+/// Terrain settings the next "New Save" creates its world with, read from `--terrain-settings`.
+#[derive(Resource, Default)]
+pub struct NewWorldTerrain(pub TerrainSettings);
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum PauseMenuAction {
     Resume,
@@ -136,7 +148,11 @@ fn setup_ui_camera(mut commands: Commands) {
     ));
 }
 
-fn setup_main_menu(mut commands: Commands, save_state: Res<SaveState>) {
+fn setup_main_menu(
+    mut commands: Commands,
+    save_state: Res<SaveState>,
+    new_world_preset: Res<NewWorldPreset>,
+) {
     commands
         .spawn((
             MainMenuRoot,
@@ -189,6 +205,12 @@ fn setup_main_menu(mut commands: Commands, save_state: Res<SaveState>) {
                     ));
 
                     spawn_menu_button(card, "New Save", MainMenuAction::NewSave, true);
+                    spawn_menu_button(
+                        card,
+                        &preset_button_label(new_world_preset.0),
+                        MainMenuAction::CyclePreset,
+                        true,
+                    );
                     spawn_menu_button(
                         card,
                         "Load Save",
@@ -284,6 +306,7 @@ fn cleanup_main_menu(mut commands: Commands, root_query: Query<Entity, With<Main
     }
 }
 
+#[allow(clippy::type_complexity, reason = "Bevy button query")]
 fn main_menu_button_visuals(
     mut button_query: Query<
         (
@@ -319,22 +342,36 @@ fn main_menu_button_visuals(
     }
 }
 
+fn preset_button_label(preset: WorldPreset) -> String {
+    format!("World Type: {}", preset.display_name())
+}
+
+#[allow(clippy::type_complexity, reason = "Bevy button query")]
 fn main_menu_actions(
     mut interaction_query: Query<
-        (&Interaction, &MainMenuButton, &InteractionDisabled),
+        (
+            &Interaction,
+            &MainMenuButton,
+            &InteractionDisabled,
+            &Children,
+        ),
         (Changed<Interaction>, With<Button>),
     >,
+    mut label_query: Query<&mut Text>,
     mut save_state: ResMut<SaveState>,
+    mut new_world_preset: ResMut<NewWorldPreset>,
+    new_world_terrain: Res<NewWorldTerrain>,
     mut next_state: ResMut<NextState<AppState>>,
+    blocks: Res<BlockRegistry>,
 ) {
-    for (interaction, button, disabled) in &mut interaction_query {
+    for (interaction, button, disabled, children) in &mut interaction_query {
         if *interaction != Interaction::Pressed || disabled.0 {
             continue;
         }
 
         match button.action {
             MainMenuAction::NewSave => {
-                save_state.start_new_world();
+                save_state.start_new_world(&blocks, new_world_preset.0, new_world_terrain.0);
                 next_state.set(AppState::LoadingWorld);
             }
             MainMenuAction::LoadSave => {
@@ -342,6 +379,14 @@ fn main_menu_actions(
                     next_state.set(AppState::LoadingWorld);
                 }
             }
+            MainMenuAction::CyclePreset => {
+                new_world_preset.0 = new_world_preset.0.next();
+                for child in children.iter() {
+                    if let Ok(mut text) = label_query.get_mut(child) {
+                        **text = preset_button_label(new_world_preset.0);
+                    }
+                }
+            }
         }
     }
 }
@@ -355,6 +400,7 @@ fn pause_input_system(
     }
 }
 
+#[allow(clippy::type_complexity, reason = "Bevy button query")]
 fn pause_menu_button_visuals(
     mut button_query: Query<
         (&Interaction, &mut BackgroundColor, &mut BorderColor),
@@ -379,6 +425,7 @@ fn pause_menu_button_visuals(
     }
 }
 
+#[allow(clippy::type_complexity, reason = "Bevy button query")]
 fn pause_menu_actions(
     mut interaction_query: Query<
         (&Interaction, &PauseMenuButton),
@@ -412,7 +459,7 @@ fn pause_menu_actions(
     }
 }
 
-fn setup_hud(mut commands: Commands) {
+fn setup_hud(mut commands: Commands, blocks: Res<BlockRegistry>) {
     commands
         .spawn((
             HudRoot,
@@ -474,7 +521,7 @@ fn setup_hud(mut commands: Commands) {
                     BackgroundColor(Color::NONE),
                 ))
                 .with_children(|hotbar| {
-                    for (index, material) in HOTBAR_MATERIALS.iter().copied().enumerate() {
+                    for (index, material) in blocks.hotbar_blocks().iter().copied().enumerate() {
                         hotbar
                             .spawn((
                                 HotbarSlot { index },
@@ -493,7 +540,7 @@ fn setup_hud(mut commands: Commands) {
                             ))
                             .with_children(|slot| {
                                 slot.spawn((
-                                    Text::new(format!("{}", index + 1)),
+                                    Text::new(format!("{}", (index + 1) % 10)),
                                     TextFont {
                                         font_size: 14.0,
                                         ..default()
@@ -502,7 +549,7 @@ fn setup_hud(mut commands: Commands) {
                                 ));
 
                                 slot.spawn((
-                                    Text::new(hotbar_material_name(material)),
+                                    Text::new(blocks.display_name(material)),
                                     TextFont {
                                         font_size: 18.0,
                                         ..default()
@@ -532,15 +579,6 @@ fn cleanup_hud(mut commands: Commands, hud_query: Query<Entity, With<HudRoot>>)
     }
 }
 
-fn hotbar_material_name(material: VoxelType) -> &'static str {
-    match material {
-        VoxelType::Grass => "Grass",
-        VoxelType::Dirt => "Dirt",
-        VoxelType::Stone => "Stone",
-        VoxelType::Air => "Air",
-    }
-}
-
 fn setup_pause_menu(mut commands: Commands) {
     commands
         .spawn((
@@ -716,6 +754,10 @@ fn cleanup_loading_screen(mut commands: Commands, root_query: Query<Entity, With
     }
 }
 
+#[allow(
+    clippy::type_complexity,
+    reason = "ParamSet of the loading screen text and bar queries"
+)]
 fn update_loading_screen(
     generation_state: Res<InitialWorldGeneration>,
     mut text_queries: ParamSet<(
@@ -724,10 +766,14 @@ fn update_loading_screen(
     )>,
 ) {
     if let Ok(mut text) = text_queries.p0().single_mut() {
-        **text = format!(
-            "Preparing World... {} / {}",
-            generation_state.completed_chunks, generation_state.total_chunks
-        );
+        **text = if generation_state.spawn_search.is_some() {
+            "Finding Spawn Point...".to_string()
+        } else {
+            format!(
+                "Preparing World... {} / {}",
+                generation_state.completed_chunks, generation_state.total_chunks
+            )
+        };
     }
 
     if let Ok(mut node) = text_queries.p1().single_mut() {
@@ -740,11 +786,17 @@ fn update_loading_screen(
     }
 }
 
+#[allow(
+    clippy::type_complexity,
+    reason = "Bevy system filling every HUD element from one ParamSet"
+)]
 fn update_hud_text(
     player_query: Query<&Transform, With<Player>>,
     interaction: Res<PlayerInteraction>,
     inventory: Res<Inventory>,
-    world: Res<crate::world::World>,
+    voxels: VoxelReader,
+    blocks: Res<BlockRegistry>,
+    view: Res<ViewDistanceSettings>,
     mut text_queries: ParamSet<(
         Query<&mut Text, With<PlayerInfoText>>,
         Query<&mut Text, With<SelectedBlockText>>,
@@ -753,32 +805,59 @@ fn update_hud_text(
         Query<(&HotbarSlotLabel, &mut TextColor)>,
     )>,
 ) {
-    if let Ok(player_transform) = player_query.single() {
-        if let Ok(mut text) = text_queries.p0().single_mut() {
-            let pos = player_transform.translation;
-            **text = format!("Position: ({:.1}, {:.1}, {:.1})", pos.x, pos.y, pos.z);
+    if let Ok(player_transform) = player_query.single()
+        && let Ok(mut text) = text_queries.p0().single_mut()
+    {
+        let pos = player_transform.translation;
+        let mut info = format!("Position: ({:.1}, {:.1}, {:.1})", pos.x, pos.y, pos.z);
+        if let Some(biome) = voxels
+            .world()
+            .generator
+            .as_ref()
+            .and_then(|generator| generator.biome_at(pos.x as f64, pos.z as f64))
+        {
+            info.push_str(&format!("\nBiome: {}", biome.display_name()));
         }
+        info.push_str(&format!(
+            "\nRender distance: {} chunks (F2/F3)\nSimulation distance: {} chunks (F4/F5)",
+            view.render_distance_chunks, view.simulation_distance_chunks
+        ));
+        **text = info;
     }
 
     if let Ok(mut text) = text_queries.p1().single_mut() {
-        if let Some(selected_pos) = interaction.selected_voxel_world_pos {
-            if let Some((chunk_coord, x, y, z)) = world.world_to_voxel(selected_pos) {
+        if let Some(selected_cell) = interaction.selected_cell {
+            if let Some((chunk_coord, x, y, z)) = voxels.world().locate_cell(selected_cell) {
                 let mut info = format!(
-                    "Selected: Chunk({}, {}) Voxel({}, {}, {})\nWorld Pos: ({:.1}, {:.1}, {:.1})",
+                    "Selected: Chunk({}, {}, {}) Voxel({}, {}, {})\nCell: ({}, {}, {})",
                     chunk_coord.x,
+                    chunk_coord.y,
                     chunk_coord.z,
                     x,
                     y,
                     z,
-                    selected_pos.x,
-                    selected_pos.y,
-                    selected_pos.z
+                    selected_cell.x,
+                    selected_cell.y,
+                    selected_cell.z
                 );
 
                 if let Some(face) = interaction.hit_face {
                     info.push_str(&format!("\nHit Face: {:?}", face));
                 }
 
+                if let Some(voxel) = voxels.get(selected_cell)
+                    && let Some(block) = blocks.get(voxel.block)
+                {
+                    info.push_str(&format!(
+                        "\nBlock: {} (hardness {:.1})",
+                        block.display_name, block.hardness
+                    ));
+                    let state = blocks.describe_state(voxel);
+                    if !state.is_empty() {
+                        info.push_str(&format!("\nState: {state}"));
+                    }
+                }
+
                 **text = info;
             } else {
                 **text = "Selected: None".to_string();
@@ -788,7 +867,8 @@ fn update_hud_text(
         }
     }
 
-    let selected_index = selected_material_index(interaction.selected_material);
+    let selected_index =
+        selected_material_index(blocks.hotbar_blocks(), interaction.selected_material);
     for (slot, mut background, mut border) in &mut text_queries.p2() {
         let is_selected = slot.index == selected_index;
         *background = if is_selected {
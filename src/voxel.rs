@@ -1,7 +1,6 @@
 pub const VOXELS_PER_METER: f32 = 16.0;
 pub const VOXEL_SIZE: f32 = 1.0 / VOXELS_PER_METER;
 
-use bevy::prelude::Color;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -38,6 +37,18 @@ impl VoxelFace {
         }
     }
 
+    /// Name used for this face in block state properties such as `facing`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoxelFace::NegativeX => "negative_x",
+            VoxelFace::PositiveX => "positive_x",
+            VoxelFace::NegativeY => "negative_y",
+            VoxelFace::PositiveY => "positive_y",
+            VoxelFace::NegativeZ => "negative_z",
+            VoxelFace::PositiveZ => "positive_z",
+        }
+    }
+
     pub fn from_normal(normal: bevy::prelude::Vec3) -> Option<Self> {
         const EPSILON: f32 = 0.5;
 
@@ -103,57 +114,33 @@ impl VoxelFace {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum VoxelType {
-    Air,
-    Stone,
-    Dirt,
-    Grass,
-}
+/// Compact numeric id of a block declared in the [`crate::block::BlockRegistry`].
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct BlockId(pub u16);
 
-impl Default for VoxelType {
-    fn default() -> Self {
-        VoxelType::Air
-    }
-}
-
-impl VoxelType {
-    pub fn is_solid(&self) -> bool {
-        match self {
-            VoxelType::Air => false,
-            _ => true,
-        }
-    }
+impl BlockId {
+    pub const AIR: BlockId = BlockId(0);
 
-    pub fn color(&self) -> Color {
-        match self {
-            VoxelType::Air => Color::NONE,
-            VoxelType::Stone => Color::srgb(0.45, 0.47, 0.50),
-            VoxelType::Dirt => Color::srgb(0.45, 0.31, 0.20),
-            VoxelType::Grass => Color::srgb(0.34, 0.62, 0.24),
-        }
+    pub fn is_air(self) -> bool {
+        self == Self::AIR
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Voxel {
-    pub voxel_type: VoxelType,
-}
-
-impl Default for Voxel {
-    fn default() -> Self {
-        Self {
-            voxel_type: VoxelType::Air,
-        }
-    }
+    pub block: BlockId,
+    /// Packed values of the properties the block declares in the registry.
+    pub state: u16,
 }
 
 impl Voxel {
-    pub fn new(voxel_type: VoxelType) -> Self {
-        Self { voxel_type }
+    pub fn new(block: BlockId) -> Self {
+        Self { block, state: 0 }
     }
 
-    pub fn is_solid(&self) -> bool {
-        self.voxel_type.is_solid()
+    pub fn with_state(block: BlockId, state: u16) -> Self {
+        Self { block, state }
     }
 }
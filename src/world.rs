@@ -1,84 +1,198 @@
+use std::sync::Arc;
+
+use bevy::ecs::system::SystemParam;
 use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
 use bevy::tasks::{AsyncComputeTaskPool, Task, futures_lite::future};
 use serde::{Deserialize, Serialize};
 
 use crate::AppState;
+use crate::block::BlockRegistry;
+use crate::generator::{ChunkGenerator, default_surface_bounds};
+use crate::light::{LightChannel, LightLevels};
+use crate::palette::PalettedVoxels;
 use crate::player::{
     EditMode, EditRequest, Inventory, NeedsPhysicsRefresh, NeedsRenderRefresh, Player,
     PlayerCamera, spawn_player,
 };
 use crate::save::{SaveState, SavedChunk};
-use crate::terrain::{TERRAIN_MAX_HEIGHT_METERS, TerrainGenerator};
-use crate::voxel::{VOXEL_SIZE, Voxel, VoxelType};
+use crate::spawn::find_spawn_position;
+use crate::voxel::{BlockId, VOXEL_SIZE, Voxel};
 
 pub const CHUNK_SIZE: usize = 32;
-pub const CHUNK_HEIGHT: usize = 256;
-pub const VISIBLE_RADIUS_METERS: f32 = 16.0;
 pub const INITIAL_LOAD_RADIUS_CHUNKS: i32 = 3;
+/// Largest render or simulation distance the in-game controls allow, in chunks.
+pub const MAX_VIEW_DISTANCE_CHUNKS: i32 = 12;
+const DEFAULT_RENDER_DISTANCE_METERS: f32 = 16.0;
+const DEFAULT_SIMULATION_DISTANCE_METERS: f32 = 8.0;
+/// Chunks stay loaded this many chunks past the load distance, so walking back and forth along
+/// the edge doesn't regenerate them every step.
+const UNLOAD_MARGIN_CHUNKS: i32 = 2;
+/// Most chunk generation tasks running at once. The rest wait in `World::queued_chunks`, so the
+/// task pool stays free for meshes and colliders and the queue can still be reordered.
+const MAX_GENERATION_TASKS: usize = 32;
+/// How much further a chunk behind the player counts than one straight ahead at the same distance
+/// when picking what to generate next, as a fraction of its distance.
+const GENERATION_BEHIND_PENALTY: f32 = 1.0;
 
 pub const CHUNK_VOXELS_SIZE: usize = CHUNK_SIZE;
-pub const CHUNK_VOXELS_HEIGHT: usize = CHUNK_HEIGHT;
+const CHUNK_VOXEL_COUNT: usize = CHUNK_VOXELS_SIZE * CHUNK_VOXELS_SIZE * CHUNK_VOXELS_SIZE;
 const PLAYER_SPAWN_CLEARANCE_METERS: f32 = 3.0;
 
 pub fn chunk_world_size() -> f32 {
     CHUNK_VOXELS_SIZE as f32 * VOXEL_SIZE
 }
 
-pub fn chunk_world_height() -> f32 {
-    CHUNK_VOXELS_HEIGHT as f32 * VOXEL_SIZE
-}
-
 pub fn chunk_world_origin(coord: ChunkCoord) -> Vec3 {
-    Vec3::new(
-        coord.x as f32 * chunk_world_size(),
-        0.0,
-        coord.z as f32 * chunk_world_size(),
-    )
+    Vec3::new(coord.x as f32, coord.y as f32, coord.z as f32) * chunk_world_size()
 }
 
-pub fn render_distance_chunks() -> i32 {
-    ((VISIBLE_RADIUS_METERS / chunk_world_size()).ceil() as i32).max(1)
+fn meters_to_chunks(meters: f32) -> i32 {
+    ((meters / chunk_world_size()).ceil() as i32).max(1)
 }
 
-pub fn initial_player_spawn_position() -> Vec3 {
+/// Default spawn column of a new world, high enough above terrain reaching `max_height_meters` to
+/// drop onto it. The spawn search starts here and falls back to it when it finds no safe ground
+/// nearby.
+pub fn initial_player_spawn_position(max_height_meters: f32) -> Vec3 {
     Vec3::new(
         chunk_world_size() * 0.25,
-        TERRAIN_MAX_HEIGHT_METERS + PLAYER_SPAWN_CLEARANCE_METERS,
+        max_height_meters + PLAYER_SPAWN_CLEARANCE_METERS,
         chunk_world_size() * 0.25,
     )
 }
 
+/// Global coordinate of the voxel containing `world_pos`.
+pub fn voxel_cell(world_pos: Vec3) -> IVec3 {
+    (world_pos / VOXEL_SIZE).floor().as_ivec3()
+}
+
+/// World position of the minimum corner of a voxel cell.
+pub fn voxel_cell_origin(cell: IVec3) -> Vec3 {
+    cell.as_vec3() * VOXEL_SIZE
+}
+
+/// Splits a global voxel cell into the chunk holding it and its coordinates inside that chunk.
+pub fn split_cell(cell: IVec3) -> (ChunkCoord, usize, usize, usize) {
+    let size = CHUNK_VOXELS_SIZE as i32;
+    let local = cell.rem_euclid(IVec3::splat(size));
+    (
+        ChunkCoord::from_cell(cell),
+        local.x as usize,
+        local.y as usize,
+        local.z as usize,
+    )
+}
+
+/// Offsets from a cell to the six cells sharing one of its faces.
+pub const FACE_NEIGHBORS: [IVec3; 6] = [
+    IVec3::NEG_X,
+    IVec3::X,
+    IVec3::NEG_Y,
+    IVec3::Y,
+    IVec3::NEG_Z,
+    IVec3::Z,
+];
+
+/// Sent for every voxel whose contents changed, so simulations such as fluids and falling blocks
+/// can react to the change and its neighbors.
+#[derive(Clone, Copy, Debug, Message)]
+pub struct VoxelChanged {
+    pub cell: IVec3,
+}
+
 #[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct DebugInfoState {
     pub enabled: bool,
 }
 
+/// How far around the player chunks are drawn and simulated, adjustable while playing. Chunks
+/// are loaded out to the larger of the two, so changing either streams chunks in or out over the
+/// next frames.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ViewDistanceSettings {
+    /// Radius within which chunk meshes are built and shown, in chunks.
+    pub render_distance_chunks: i32,
+    /// Radius within which chunks get colliders and run fluid and falling block updates, in
+    /// chunks.
+    pub simulation_distance_chunks: i32,
+}
+
+impl Default for ViewDistanceSettings {
+    fn default() -> Self {
+        Self {
+            render_distance_chunks: meters_to_chunks(DEFAULT_RENDER_DISTANCE_METERS),
+            simulation_distance_chunks: meters_to_chunks(DEFAULT_SIMULATION_DISTANCE_METERS),
+        }
+    }
+}
+
+impl ViewDistanceSettings {
+    pub fn load_distance_chunks(&self) -> i32 {
+        self.render_distance_chunks
+            .max(self.simulation_distance_chunks)
+    }
+
+    pub fn unload_distance_chunks(&self) -> i32 {
+        self.load_distance_chunks() + UNLOAD_MARGIN_CHUNKS
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ChunkCoord {
     pub x: i32,
+    pub y: i32,
     pub z: i32,
 }
 
 impl ChunkCoord {
-    pub fn new(x: i32, z: i32) -> Self {
-        Self { x, z }
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
     }
 
     pub fn from_world_pos(world_pos: Vec3) -> Self {
         let chunk_size_world = chunk_world_size();
         Self {
             x: (world_pos.x / chunk_size_world).floor() as i32,
+            y: (world_pos.y / chunk_size_world).floor() as i32,
             z: (world_pos.z / chunk_size_world).floor() as i32,
         }
     }
+
+    /// Chunk holding a global voxel cell.
+    pub fn from_cell(cell: IVec3) -> Self {
+        let chunk = cell.div_euclid(IVec3::splat(CHUNK_VOXELS_SIZE as i32));
+        Self::new(chunk.x, chunk.y, chunk.z)
+    }
+
+    /// Global cell of the chunk's minimum corner voxel.
+    pub fn origin_cell(self) -> IVec3 {
+        IVec3::new(self.x, self.y, self.z) * CHUNK_VOXELS_SIZE as i32
+    }
+
+    pub fn offset(self, dx: i32, dy: i32, dz: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+
+    pub fn distance_squared(self, other: ChunkCoord) -> i32 {
+        let dx = self.x - other.x;
+        let dy = self.y - other.y;
+        let dz = self.z - other.z;
+        dx * dx + dy * dy + dz * dz
+    }
 }
 
+/// A `CHUNK_SIZE`-voxel cube of the world.
 #[derive(Component, Clone)]
 pub struct Chunk {
     pub coord: ChunkCoord,
-    pub voxels: Vec<Voxel>,
+    voxels: PalettedVoxels,
+    block_light: LightLevels,
+    sky_light: LightLevels,
+    /// Bumped by every voxel change; colliders and meshes built from an older one are stale.
     pub revision: u64,
+    /// Bumped by every light change, which only affects meshes.
+    pub light_revision: u64,
     pub modified: bool,
 }
 
@@ -86,11 +200,11 @@ impl Chunk {
     pub fn new(coord: ChunkCoord) -> Self {
         Self {
             coord,
-            voxels: vec![
-                Voxel::default();
-                CHUNK_VOXELS_SIZE * CHUNK_VOXELS_HEIGHT * CHUNK_VOXELS_SIZE
-            ],
+            voxels: PalettedVoxels::new(CHUNK_VOXEL_COUNT, Voxel::default()),
+            block_light: LightLevels::uniform(CHUNK_VOXEL_COUNT, 0),
+            sky_light: LightLevels::uniform(CHUNK_VOXEL_COUNT, 0),
             revision: 0,
+            light_revision: 0,
             modified: false,
         }
     }
@@ -99,30 +213,137 @@ impl Chunk {
         x + z * CHUNK_VOXELS_SIZE + y * CHUNK_VOXELS_SIZE * CHUNK_VOXELS_SIZE
     }
 
-    pub fn get_voxel(&self, x: usize, y: usize, z: usize) -> Option<&Voxel> {
-        if x < CHUNK_VOXELS_SIZE && y < CHUNK_VOXELS_HEIGHT && z < CHUNK_VOXELS_SIZE {
-            Some(&self.voxels[Self::voxel_index(x, y, z)])
+    pub fn get_voxel(&self, x: usize, y: usize, z: usize) -> Option<Voxel> {
+        if x < CHUNK_VOXELS_SIZE && y < CHUNK_VOXELS_SIZE && z < CHUNK_VOXELS_SIZE {
+            Some(self.voxels.get(Self::voxel_index(x, y, z)))
         } else {
             None
         }
     }
 
     pub fn set_voxel(&mut self, x: usize, y: usize, z: usize, voxel: Voxel) {
-        if x < CHUNK_VOXELS_SIZE && y < CHUNK_VOXELS_HEIGHT && z < CHUNK_VOXELS_SIZE {
-            let index = Self::voxel_index(x, y, z);
-            if self.voxels[index] != voxel {
-                self.voxels[index] = voxel;
-                self.revision += 1;
-                self.modified = true;
-            }
+        if x < CHUNK_VOXELS_SIZE && y < CHUNK_VOXELS_SIZE && z < CHUNK_VOXELS_SIZE {
+            self.set_voxel_by_index(Self::voxel_index(x, y, z), voxel);
+        }
+    }
+
+    /// Sets a voxel addressed by its index in `voxels()` order.
+    pub fn set_voxel_by_index(&mut self, index: usize, voxel: Voxel) {
+        if self.voxels.set(index, voxel) {
+            self.revision += 1;
+            self.modified = true;
         }
     }
+
+    /// Returns the light level of a voxel in one channel, or 0 outside the chunk.
+    pub fn light(&self, channel: LightChannel, x: usize, y: usize, z: usize) -> u8 {
+        if x < CHUNK_VOXELS_SIZE && y < CHUNK_VOXELS_SIZE && z < CHUNK_VOXELS_SIZE {
+            self.light_levels(channel).get(Self::voxel_index(x, y, z))
+        } else {
+            0
+        }
+    }
+
+    /// Sets the light level of a voxel and returns whether it changed. Light is derived from the
+    /// voxels, so it bumps the light revision for a mesh rebuild without marking the chunk for saving.
+    pub fn set_light(
+        &mut self,
+        channel: LightChannel,
+        x: usize,
+        y: usize,
+        z: usize,
+        level: u8,
+    ) -> bool {
+        if x >= CHUNK_VOXELS_SIZE || y >= CHUNK_VOXELS_SIZE || z >= CHUNK_VOXELS_SIZE {
+            return false;
+        }
+        if !self
+            .light_levels_mut(channel)
+            .set(Self::voxel_index(x, y, z), level)
+        {
+            return false;
+        }
+
+        self.light_revision += 1;
+        true
+    }
+
+    /// Sets every voxel of one channel to the same light level.
+    pub fn fill_light(&mut self, channel: LightChannel, level: u8) {
+        *self.light_levels_mut(channel) = LightLevels::uniform(CHUNK_VOXEL_COUNT, level);
+        self.light_revision += 1;
+    }
+
+    /// Returns the shared level when every voxel of the channel has the same light.
+    pub fn uniform_light(&self, channel: LightChannel) -> Option<u8> {
+        self.light_levels(channel).uniform_level()
+    }
+
+    fn light_levels(&self, channel: LightChannel) -> &LightLevels {
+        match channel {
+            LightChannel::Block => &self.block_light,
+            LightChannel::Sky => &self.sky_light,
+        }
+    }
+
+    fn light_levels_mut(&mut self, channel: LightChannel) -> &mut LightLevels {
+        match channel {
+            LightChannel::Block => &mut self.block_light,
+            LightChannel::Sky => &mut self.sky_light,
+        }
+    }
+
+    /// Iterates every voxel in x, then z, then y order.
+    pub fn voxels(&self) -> impl Iterator<Item = Voxel> + '_ {
+        self.voxels.iter()
+    }
+
+    /// FNV-1a hash of every voxel's block and state in `voxels()` order. It depends on nothing
+    /// but the contents, so it stays the same across runs, threads and builds, and a changed
+    /// hash for a freshly generated chunk means generation is no longer deterministic.
+    pub fn content_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        self.voxels()
+            .flat_map(|voxel| [voxel.block.0.to_le_bytes(), voxel.state.to_le_bytes()])
+            .flatten()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// Cheap palette check that rules out chunks holding no matching voxel.
+    pub fn may_contain(&self, predicate: impl Fn(Voxel) -> bool) -> bool {
+        self.voxels.palette_contains(predicate)
+    }
+
+    /// Returns true when the chunk holds nothing but air.
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_uniform() && self.voxels.get(0).block.is_air()
+    }
+
+    pub fn compact(&mut self) {
+        self.voxels.compact();
+    }
+
+    pub fn heap_size(&self) -> usize {
+        self.voxels.heap_size() + self.block_light.heap_size() + self.sky_light.heap_size()
+    }
 }
 
 #[derive(Resource)]
 pub struct World {
     pub chunks: HashMap<ChunkCoord, Entity>,
+    /// Chunks being generated. Dropping a task cancels it.
     pub pending_chunks: HashMap<ChunkCoord, Task<Chunk>>,
+    /// Chunks waiting for a free generation slot.
+    pub queued_chunks: HashSet<ChunkCoord>,
+    /// Generator of the current world, shared by every generation task so its noise and caches
+    /// are built once per session.
+    pub generator: Option<Arc<dyn ChunkGenerator>>,
+    /// Chunk the player is in, which view distances are measured from. `None` until the player
+    /// spawns, and while it is every loaded chunk counts as in range.
+    pub focus: Option<ChunkCoord>,
 }
 
 impl Default for World {
@@ -130,11 +351,14 @@ impl Default for World {
         Self {
             chunks: HashMap::new(),
             pending_chunks: HashMap::new(),
+            queued_chunks: HashSet::new(),
+            generator: None,
+            focus: None,
         }
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct InitialWorldGeneration {
     pub started: bool,
     pub finished: bool,
@@ -142,86 +366,148 @@ pub struct InitialWorldGeneration {
     pub completed_chunks: usize,
     pub target_chunks: HashSet<ChunkCoord>,
     pub spawn_position: Option<Vec3>,
+    /// The search for a new player's spawn point, running while the loading screen shows.
+    pub spawn_search: Option<Task<Vec3>>,
 }
 
-impl Default for InitialWorldGeneration {
-    fn default() -> Self {
-        Self {
-            started: false,
-            finished: false,
-            total_chunks: 0,
-            completed_chunks: 0,
-            target_chunks: HashSet::default(),
-            spawn_position: None,
-        }
+impl World {
+    /// Splits a global cell into its chunk and local coordinates, if that chunk is loaded.
+    pub fn locate_cell(&self, cell: IVec3) -> Option<(ChunkCoord, usize, usize, usize)> {
+        let located = split_cell(cell);
+        self.chunks.contains_key(&located.0).then_some(located)
+    }
+
+    /// Surface bounds of the world's generator, or of the default terrain before one is set.
+    pub fn surface_bounds(&self) -> (f32, f32) {
+        self.generator
+            .as_ref()
+            .map_or_else(default_surface_bounds, |generator| {
+                generator.surface_bounds()
+            })
+    }
+
+    /// Whether `coord` is within `distance` chunks of the player.
+    pub fn within_distance(&self, coord: ChunkCoord, distance: i32) -> bool {
+        self.focus
+            .is_none_or(|focus| coord.distance_squared(focus) <= distance * distance)
+    }
+
+    /// Whether `cell` is in a loaded chunk beyond the simulation distance, where fluids and
+    /// falling blocks wait until the player comes closer.
+    pub fn simulation_paused(&self, cell: IVec3, view: &ViewDistanceSettings) -> bool {
+        let coord = ChunkCoord::from_cell(cell);
+        self.chunks.contains_key(&coord)
+            && !self.within_distance(coord, view.simulation_distance_chunks)
     }
 }
 
-impl World {
-    /// Converts world coordinates to chunk coordinates and voxel indices.
-    pub fn world_to_voxel(&self, world_pos: Vec3) -> Option<(ChunkCoord, usize, usize, usize)> {
-        let chunk_coord = ChunkCoord::from_world_pos(world_pos);
+/// Voxel reads by global integer cell, shared by `VoxelReader` and `VoxelWorld`, so callers never
+/// convert float positions to chunk indices themselves.
+pub trait VoxelAccess {
+    fn world(&self) -> &World;
 
-        if !self.chunks.contains_key(&chunk_coord) {
-            return None;
-        }
+    fn chunk(&self, coord: ChunkCoord) -> Option<&Chunk>;
+
+    /// Returns true when the chunk holding the cell is loaded.
+    fn is_loaded(&self, cell: IVec3) -> bool {
+        self.world().locate_cell(cell).is_some()
+    }
 
-        let chunk_origin = chunk_world_origin(chunk_coord);
+    /// Returns the voxel in a cell, or `None` if its chunk is not loaded.
+    fn get(&self, cell: IVec3) -> Option<Voxel> {
+        let (coord, x, y, z) = split_cell(cell);
+        self.chunk(coord)?.get_voxel(x, y, z)
+    }
 
-        let local_x = world_pos.x - chunk_origin.x;
-        let local_y = world_pos.y;
-        let local_z = world_pos.z - chunk_origin.z;
+    /// Iterates the six cells sharing a face with `cell`, with their voxels where loaded.
+    fn neighbors(&self, cell: IVec3) -> impl Iterator<Item = (IVec3, Option<Voxel>)> + '_ {
+        FACE_NEIGHBORS.into_iter().map(move |offset| {
+            let neighbor = cell + offset;
+            (neighbor, self.get(neighbor))
+        })
+    }
 
-        if local_x < 0.0 || local_y < 0.0 || local_z < 0.0 {
-            return None;
-        }
+    /// Iterates the loaded voxels in the box between `min` and `max`, both inclusive.
+    fn region(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (IVec3, Voxel)> + '_ {
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| {
+                (min.z..=max.z).filter_map(move |z| {
+                    let cell = IVec3::new(x, y, z);
+                    self.get(cell).map(|voxel| (cell, voxel))
+                })
+            })
+        })
+    }
+}
 
-        let voxel_x = (local_x / VOXEL_SIZE).floor() as usize;
-        let voxel_y = (local_y / VOXEL_SIZE).floor() as usize;
-        let voxel_z = (local_z / VOXEL_SIZE).floor() as usize;
+/// Reads voxels of the loaded chunks. Systems that only look at voxels use this rather than
+/// `VoxelWorld` so they can run in parallel with each other.
+#[derive(SystemParam)]
+pub struct VoxelReader<'w, 's> {
+    world: Res<'w, World>,
+    chunks: Query<'w, 's, &'static Chunk>,
+}
 
-        if voxel_x < CHUNK_VOXELS_SIZE
-            && voxel_y < CHUNK_VOXELS_HEIGHT
-            && voxel_z < CHUNK_VOXELS_SIZE
-        {
-            Some((chunk_coord, voxel_x, voxel_y, voxel_z))
-        } else {
-            None
-        }
+impl VoxelAccess for VoxelReader<'_, '_> {
+    fn world(&self) -> &World {
+        &self.world
     }
 
-    /// Returns the voxel at a world position.
-    pub fn get_voxel_at_world(
-        &self,
-        world_pos: Vec3,
-        chunk_query: &Query<&Chunk>,
-    ) -> Option<Voxel> {
-        if let Some((chunk_coord, x, y, z)) = self.world_to_voxel(world_pos) {
-            if let Some(chunk_entity) = self.chunks.get(&chunk_coord) {
-                if let Ok(chunk) = chunk_query.get(*chunk_entity) {
-                    return chunk.get_voxel(x, y, z).copied();
-                }
-            }
-        }
-        None
+    fn chunk(&self, coord: ChunkCoord) -> Option<&Chunk> {
+        let entity = self.world.chunks.get(&coord)?;
+        self.chunks.get(*entity).ok()
     }
+}
 
-    /// Sets the voxel at a world position.
-    pub fn set_voxel_at_world(
-        &self,
-        world_pos: Vec3,
-        voxel: Voxel,
-        chunk_query: &mut Query<&mut Chunk>,
-    ) -> bool {
-        if let Some((chunk_coord, x, y, z)) = self.world_to_voxel(world_pos) {
-            if let Some(chunk_entity) = self.chunks.get(&chunk_coord) {
-                if let Ok(mut chunk) = chunk_query.get_mut(*chunk_entity) {
-                    chunk.set_voxel(x, y, z, voxel);
-                    return true;
-                }
-            }
-        }
-        false
+/// Reads and writes voxels of the loaded chunks by global integer cell.
+#[derive(SystemParam)]
+pub struct VoxelWorld<'w, 's> {
+    world: Res<'w, World>,
+    chunks: Query<'w, 's, &'static mut Chunk>,
+}
+
+impl VoxelAccess for VoxelWorld<'_, '_> {
+    fn world(&self) -> &World {
+        &self.world
+    }
+
+    fn chunk(&self, coord: ChunkCoord) -> Option<&Chunk> {
+        let entity = self.world.chunks.get(&coord)?;
+        self.chunks.get(*entity).ok()
+    }
+}
+
+impl VoxelWorld<'_, '_> {
+    pub fn chunk_mut(&mut self, coord: ChunkCoord) -> Option<Mut<'_, Chunk>> {
+        let entity = self.world.chunks.get(&coord)?;
+        self.chunks.get_mut(*entity).ok()
+    }
+
+    /// Sets the voxel in a cell and returns whether its chunk is loaded.
+    pub fn set(&mut self, cell: IVec3, voxel: Voxel) -> bool {
+        let (coord, x, y, z) = split_cell(cell);
+        let Some(mut chunk) = self.chunk_mut(coord) else {
+            return false;
+        };
+        chunk.set_voxel(x, y, z, voxel);
+        true
+    }
+}
+
+/// Reports voxels changed by the world's own simulations: flags the chunks that need rebuilding,
+/// marks the save dirty and sends `VoxelChanged` so neighbors can react.
+#[derive(SystemParam)]
+pub struct VoxelChanges<'w, 's> {
+    commands: Commands<'w, 's>,
+    save_state: ResMut<'w, SaveState>,
+    messages: MessageWriter<'w, VoxelChanged>,
+}
+
+impl VoxelChanges<'_, '_> {
+    pub fn report(&mut self, world: &World, cell: IVec3) {
+        mark_chunk_for_update(&mut self.commands, world, cell);
+        self.save_state.dirty = true;
+        self.messages.write(VoxelChanged { cell });
     }
 }
 
@@ -231,8 +517,10 @@ impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<World>()
             .init_resource::<DebugInfoState>()
+            .init_resource::<ViewDistanceSettings>()
             .init_resource::<InitialWorldGeneration>()
             .add_message::<EditRequest>()
+            .add_message::<VoxelChanged>()
             .add_systems(OnEnter(AppState::MainMenu), cleanup_player_session)
             .add_systems(OnEnter(AppState::MainMenu), cleanup_world_session)
             .add_systems(
@@ -243,6 +531,8 @@ impl Plugin for WorldPlugin {
             .add_systems(
                 Update,
                 (
+                    resolve_spawn_search,
+                    dispatch_chunk_generation_system,
                     complete_pending_chunk_generation_system,
                     complete_initial_world_generation,
                 )
@@ -252,11 +542,16 @@ impl Plugin for WorldPlugin {
             .add_systems(
                 Update,
                 (
-                    complete_pending_chunk_generation_system,
-                    chunk_loading_system,
+                    (
+                        chunk_loading_system,
+                        dispatch_chunk_generation_system,
+                        complete_pending_chunk_generation_system,
+                    )
+                        .chain(),
                     chunk_unloading_system,
                     apply_edit_requests_system,
                     toggle_debug_info_system,
+                    adjust_view_distance_system,
                 )
                     .run_if(in_state(AppState::InGame)),
             );
@@ -265,6 +560,7 @@ impl Plugin for WorldPlugin {
 
 fn finish_world_loading(
     mut commands: Commands,
+    world: Res<World>,
     generation_state: Res<InitialWorldGeneration>,
     save_state: Res<SaveState>,
     mut inventory: ResMut<Inventory>,
@@ -279,7 +575,7 @@ fn finish_world_loading(
         &mut commands,
         generation_state
             .spawn_position
-            .unwrap_or_else(initial_player_spawn_position),
+            .unwrap_or_else(|| initial_player_spawn_position(world.surface_bounds().1)),
     );
 }
 
@@ -293,12 +589,16 @@ fn prepare_world_session(
     }
     world.chunks.clear();
     world.pending_chunks.clear();
+    world.queued_chunks.clear();
+    world.generator = None;
+    world.focus = None;
     generation_state.started = false;
     generation_state.finished = false;
     generation_state.total_chunks = 0;
     generation_state.completed_chunks = 0;
     generation_state.target_chunks.clear();
     generation_state.spawn_position = None;
+    generation_state.spawn_search = None;
 }
 
 fn cleanup_world_session(
@@ -311,12 +611,16 @@ fn cleanup_world_session(
     }
     world.chunks.clear();
     world.pending_chunks.clear();
+    world.queued_chunks.clear();
+    world.generator = None;
+    world.focus = None;
     generation_state.started = false;
     generation_state.finished = false;
     generation_state.total_chunks = 0;
     generation_state.completed_chunks = 0;
     generation_state.target_chunks.clear();
     generation_state.spawn_position = None;
+    generation_state.spawn_search = None;
 }
 
 fn cleanup_player_session(
@@ -333,29 +637,28 @@ fn cleanup_player_session(
     }
 }
 
-fn queue_chunk_generation(
-    world: &mut World,
-    coord: ChunkCoord,
-    saved_chunk: Option<SavedChunk>,
-    seed: u32,
-) {
-    if world.chunks.contains_key(&coord) || world.pending_chunks.contains_key(&coord) {
-        return;
+fn queue_chunk_generation(world: &mut World, coord: ChunkCoord) {
+    if !world.chunks.contains_key(&coord) && !world.pending_chunks.contains_key(&coord) {
+        world.queued_chunks.insert(coord);
     }
+}
+
+fn start_chunk_generation(world: &mut World, coord: ChunkCoord, saved_chunk: Option<SavedChunk>) {
+    let Some(generator) = world.generator.clone() else {
+        return;
+    };
 
     let task_pool = AsyncComputeTaskPool::get();
     let task = task_pool.spawn(async move {
-        let terrain_generator = TerrainGenerator::new(seed);
         let mut chunk = Chunk::new(coord);
-        terrain_generator.generate_chunk(&mut chunk);
+        generator.generate_chunk(&mut chunk);
         if let Some(saved_chunk) = saved_chunk {
-            for (index, voxel_type) in saved_chunk.voxels.iter().copied().enumerate() {
-                if index < chunk.voxels.len() {
-                    chunk.voxels[index] = Voxel::new(voxel_type);
-                }
+            for (index, voxel) in saved_chunk.voxels.iter().copied().enumerate() {
+                chunk.set_voxel_by_index(index, voxel);
             }
             chunk.modified = true;
         }
+        chunk.compact();
         chunk
     });
 
@@ -366,24 +669,60 @@ fn start_initial_world_generation(
     mut world: ResMut<World>,
     mut generation_state: ResMut<InitialWorldGeneration>,
     save_state: Res<SaveState>,
+    blocks: Res<BlockRegistry>,
 ) {
     if generation_state.started {
         return;
     }
 
     generation_state.started = true;
-    let spawn_position = save_state
-        .initial_player_translation()
-        .unwrap_or_else(initial_player_spawn_position);
-    let target_chunks = initial_target_chunks(ChunkCoord::from_world_pos(spawn_position));
+    let generator = save_state
+        .preset
+        .generator(save_state.seed, save_state.terrain, &blocks);
+    world.generator = Some(generator.clone());
+    if let Some(spawn_position) = save_state.initial_player_translation() {
+        queue_initial_chunks(&mut world, &mut generation_state, spawn_position);
+        return;
+    }
+
+    // The search can sample many columns of terrain, so like chunk generation it runs on the task
+    // pool and `resolve_spawn_search` picks up the result.
+    let blocks = blocks.clone();
+    let task_pool = AsyncComputeTaskPool::get();
+    generation_state.spawn_search = Some(task_pool.spawn(async move {
+        let origin = initial_player_spawn_position(generator.surface_bounds().1);
+        find_spawn_position(generator.as_ref(), &blocks, origin).unwrap_or(origin)
+    }));
+}
+
+/// Queues the chunks around the spawn point once the search for it has finished.
+fn resolve_spawn_search(
+    mut world: ResMut<World>,
+    mut generation_state: ResMut<InitialWorldGeneration>,
+) {
+    let Some(task) = generation_state.spawn_search.as_mut() else {
+        return;
+    };
+    let Some(spawn_position) = future::block_on(future::poll_once(task)) else {
+        return;
+    };
+
+    generation_state.spawn_search = None;
+    queue_initial_chunks(&mut world, &mut generation_state, spawn_position);
+}
+
+fn queue_initial_chunks(
+    world: &mut World,
+    generation_state: &mut InitialWorldGeneration,
+    spawn_position: Vec3,
+) {
+    let target_chunks = initial_target_chunks(
+        ChunkCoord::from_world_pos(spawn_position),
+        world.surface_bounds().0,
+    );
 
     for &coord in &target_chunks {
-        queue_chunk_generation(
-            &mut world,
-            coord,
-            save_state.edited_chunks.get(&coord).cloned(),
-            save_state.seed,
-        );
+        queue_chunk_generation(world, coord);
     }
 
     generation_state.total_chunks = target_chunks.len();
@@ -392,26 +731,34 @@ fn start_initial_world_generation(
     generation_state.spawn_position = Some(spawn_position);
 }
 
-fn initial_target_chunks(spawn_chunk: ChunkCoord) -> HashSet<ChunkCoord> {
-    let mut target_chunks = HashSet::default();
+fn initial_target_chunks(spawn_chunk: ChunkCoord, min_height_meters: f32) -> HashSet<ChunkCoord> {
+    let mut target_chunks = chunks_within_radius(spawn_chunk, INITIAL_LOAD_RADIUS_CHUNKS);
 
-    for x in
-        (spawn_chunk.x - INITIAL_LOAD_RADIUS_CHUNKS)..=(spawn_chunk.x + INITIAL_LOAD_RADIUS_CHUNKS)
-    {
-        for z in (spawn_chunk.z - INITIAL_LOAD_RADIUS_CHUNKS)
-            ..=(spawn_chunk.z + INITIAL_LOAD_RADIUS_CHUNKS)
-        {
-            let dx = x - spawn_chunk.x;
-            let dz = z - spawn_chunk.z;
-            if dx * dx + dz * dz > INITIAL_LOAD_RADIUS_CHUNKS * INITIAL_LOAD_RADIUS_CHUNKS {
-                continue;
-            }
+    // A spawn point that fell back to the sky sits above the terrain, so also load the column
+    // beneath it down to the lowest possible surface to give the player something to land on.
+    let lowest_surface_chunk = ChunkCoord::from_world_pos(Vec3::new(0.0, min_height_meters, 0.0)).y;
+    for y in lowest_surface_chunk..spawn_chunk.y {
+        target_chunks.insert(ChunkCoord::new(spawn_chunk.x, y, spawn_chunk.z));
+    }
+
+    target_chunks
+}
+
+fn chunks_within_radius(center: ChunkCoord, radius: i32) -> HashSet<ChunkCoord> {
+    let mut chunks = HashSet::default();
 
-            target_chunks.insert(ChunkCoord::new(x, z));
+    for dx in -radius..=radius {
+        for dy in -radius..=radius {
+            for dz in -radius..=radius {
+                let coord = center.offset(dx, dy, dz);
+                if coord.distance_squared(center) <= radius * radius {
+                    chunks.insert(coord);
+                }
+            }
         }
     }
 
-    target_chunks
+    chunks
 }
 
 fn complete_initial_world_generation(
@@ -452,17 +799,21 @@ fn complete_pending_chunk_generation_system(
 
     if let Ok(player_transform) = player_query.single() {
         let player_chunk = ChunkCoord::from_world_pos(player_transform.translation);
-        ready_chunks.sort_by_key(|(coord, _)| {
-            let dx = coord.x - player_chunk.x;
-            let dz = coord.z - player_chunk.z;
-            dx * dx + dz * dz
-        });
+        ready_chunks.sort_by_key(|(coord, _)| coord.distance_squared(player_chunk));
     }
 
     for (coord, chunk) in ready_chunks {
         world.pending_chunks.remove(&coord);
+        // Chunks past the render distance only ever get a collider, which needs the chunk's
+        // transform as much as a mesh does.
         let entity = commands
-            .spawn((chunk, NeedsRenderRefresh, NeedsPhysicsRefresh))
+            .spawn((
+                chunk,
+                Transform::from_translation(chunk_world_origin(coord)),
+                GlobalTransform::default(),
+                NeedsRenderRefresh,
+                NeedsPhysicsRefresh,
+            ))
             .id();
         world.chunks.insert(coord, entity);
         if generation_state.target_chunks.contains(&coord) {
@@ -474,30 +825,70 @@ fn complete_pending_chunk_generation_system(
 fn chunk_loading_system(
     mut world: ResMut<World>,
     player_query: Query<&Transform, With<Player>>,
-    save_state: Res<SaveState>,
+    view: Res<ViewDistanceSettings>,
 ) {
     if let Ok(player_transform) = player_query.single() {
         let player_chunk = ChunkCoord::from_world_pos(player_transform.translation);
-        let render_distance = render_distance_chunks();
-
-        for x in (player_chunk.x - render_distance)..=(player_chunk.x + render_distance) {
-            for z in (player_chunk.z - render_distance)..=(player_chunk.z + render_distance) {
-                let dx = x - player_chunk.x;
-                let dz = z - player_chunk.z;
-                if dx * dx + dz * dz > render_distance * render_distance {
-                    continue;
-                }
+        if world.focus != Some(player_chunk) {
+            world.focus = Some(player_chunk);
+        }
 
-                let coord = ChunkCoord::new(x, z);
-                queue_chunk_generation(
-                    &mut world,
-                    coord,
-                    save_state.edited_chunks.get(&coord).cloned(),
-                    save_state.seed,
-                );
-            }
+        let load_distance = view.load_distance_chunks();
+        for coord in chunks_within_radius(player_chunk, load_distance) {
+            queue_chunk_generation(&mut world, coord);
         }
+
+        // Forget chunks the player moved away from before they were generated.
+        let in_range =
+            |coord: &ChunkCoord| coord.distance_squared(player_chunk) <= load_distance.pow(2);
+        world.queued_chunks.retain(in_range);
+        world.pending_chunks.retain(|coord, _| in_range(coord));
+    }
+}
+
+/// Starts generating the queued chunks that matter most, until `MAX_GENERATION_TASKS` are in
+/// flight. Nearer chunks go first, and of two chunks at the same distance the one the camera
+/// faces.
+fn dispatch_chunk_generation_system(
+    mut world: ResMut<World>,
+    save_state: Res<SaveState>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+) {
+    let free_slots = MAX_GENERATION_TASKS.saturating_sub(world.pending_chunks.len());
+    if free_slots == 0 || world.queued_chunks.is_empty() {
+        return;
+    }
+
+    let forward = camera_query
+        .single()
+        .map_or(Vec3::ZERO, |camera| camera.forward().as_vec3());
+    let mut queued = world.queued_chunks.iter().copied().collect::<Vec<_>>();
+    if let Some(focus) = world.focus {
+        queued.sort_by_cached_key(|coord| {
+            (generation_priority(*coord, focus, forward) * 1024.0) as i64
+        });
+    }
+
+    for coord in queued.into_iter().take(free_slots) {
+        world.queued_chunks.remove(&coord);
+        start_chunk_generation(
+            &mut world,
+            coord,
+            save_state.edited_chunks.get(&coord).cloned(),
+        );
+    }
+}
+
+/// Generation order of a chunk around `focus`, lowest first: its distance in chunks, stretched by
+/// up to `GENERATION_BEHIND_PENALTY` the further it lies behind the `forward` direction.
+fn generation_priority(coord: ChunkCoord, focus: ChunkCoord, forward: Vec3) -> f32 {
+    let offset = IVec3::new(coord.x - focus.x, coord.y - focus.y, coord.z - focus.z).as_vec3();
+    let distance = offset.length();
+    if distance == 0.0 {
+        return 0.0;
     }
+    let facing = offset.dot(forward) / distance;
+    distance * (1.0 + GENERATION_BEHIND_PENALTY * (1.0 - facing) / 2.0)
 }
 
 fn chunk_unloading_system(
@@ -506,19 +897,16 @@ fn chunk_unloading_system(
     player_query: Query<&Transform, With<Player>>,
     chunk_query: Query<&Chunk>,
     mut save_state: ResMut<SaveState>,
+    view: Res<ViewDistanceSettings>,
 ) {
     if let Ok(player_transform) = player_query.single() {
         let player_chunk = ChunkCoord::from_world_pos(player_transform.translation);
-        let unload_distance = render_distance_chunks() + 2;
+        let unload_distance = view.unload_distance_chunks();
 
         let mut chunks_to_unload = Vec::new();
 
         for (&chunk_coord, &chunk_entity) in world.chunks.iter() {
-            let distance_x = (chunk_coord.x - player_chunk.x).abs();
-            let distance_z = (chunk_coord.z - player_chunk.z).abs();
-
-            if distance_x * distance_x + distance_z * distance_z > unload_distance * unload_distance
-            {
+            if chunk_coord.distance_squared(player_chunk) > unload_distance * unload_distance {
                 chunks_to_unload.push((chunk_coord, chunk_entity));
             }
         }
@@ -535,91 +923,292 @@ fn chunk_unloading_system(
 
 fn apply_edit_requests_system(
     mut commands: Commands,
-    world: Res<World>,
-    mut chunk_query: Query<&mut Chunk>,
+    mut voxels: VoxelWorld,
     mut edit_requests: MessageReader<EditRequest>,
+    mut voxel_changes: MessageWriter<VoxelChanged>,
     mut save_state: ResMut<SaveState>,
     mut inventory: ResMut<Inventory>,
+    blocks: Res<BlockRegistry>,
 ) {
     for request in edit_requests.read() {
-        let changed_positions =
-            apply_edit_request(&world, &mut chunk_query, &mut inventory, request);
-        for world_pos in changed_positions {
-            mark_chunk_for_update(&mut commands, &world, world_pos);
+        let changed_cells = apply_edit_request(&mut voxels, &mut inventory, &blocks, request);
+        for cell in changed_cells {
+            mark_chunk_for_update(&mut commands, voxels.world(), cell);
+            voxel_changes.write(VoxelChanged { cell });
         }
-        if !request.positions.is_empty() {
+        if !request.cells.is_empty() {
             save_state.dirty = true;
         }
     }
 }
 
 fn apply_edit_request(
-    world: &World,
-    chunk_query: &mut Query<&mut Chunk>,
+    voxels: &mut VoxelWorld,
     inventory: &mut Inventory,
+    blocks: &BlockRegistry,
     request: &EditRequest,
-) -> Vec<Vec3> {
-    let mut changed_positions = Vec::new();
+) -> Vec<IVec3> {
+    let mut changed_cells = Vec::new();
 
     for operation in &request.operations {
         match operation.mode {
             EditMode::Place => {
-                if operation.voxel_type == VoxelType::Air {
+                if operation.voxel.block.is_air() {
                     continue;
                 }
 
-                if !inventory.try_remove(operation.voxel_type, 1) {
+                if !inventory.try_remove(operation.voxel.block, 1) {
                     continue;
                 }
 
-                if world.set_voxel_at_world(
-                    operation.position,
-                    Voxel::new(operation.voxel_type),
-                    chunk_query,
-                ) {
-                    changed_positions.push(operation.position);
+                if voxels.set(operation.cell, operation.voxel) {
+                    changed_cells.push(operation.cell);
                 } else {
-                    inventory.add(operation.voxel_type, 1);
+                    inventory.add(operation.voxel.block, 1);
                 }
             }
             EditMode::Break => {
-                if let Some(previous) =
-                    world.get_voxel_at_world(operation.position, &chunk_query.as_readonly())
+                if let Some(previous) = voxels.get(operation.cell)
+                    && blocks.is_solid(previous.block)
+                    && voxels.set(operation.cell, Voxel::new(BlockId::AIR))
                 {
-                    if previous.is_solid()
-                        && world.set_voxel_at_world(
-                            operation.position,
-                            Voxel::new(VoxelType::Air),
-                            chunk_query,
-                        )
-                    {
-                        inventory.add(previous.voxel_type, 1);
-                        changed_positions.push(operation.position);
-                    }
+                    inventory.add(previous.block, 1);
+                    changed_cells.push(operation.cell);
                 }
             }
         }
     }
 
-    changed_positions
+    changed_cells
+}
+
+/// Flags the chunk containing `cell`, and any neighbor sharing the edited face, for a rebuild.
+pub fn mark_chunk_for_update(commands: &mut Commands, world: &World, cell: IVec3) {
+    if let Some((chunk_coord, voxel_x, voxel_y, voxel_z)) = world.locate_cell(cell) {
+        for dirty_chunk in chunks_sharing_voxel(chunk_coord, voxel_x, voxel_y, voxel_z) {
+            if let Some(chunk_entity) = world.chunks.get(&dirty_chunk) {
+                commands
+                    .entity(*chunk_entity)
+                    .insert((NeedsRenderRefresh, NeedsPhysicsRefresh));
+            }
+        }
+    }
+}
+
+/// Returns the chunk holding a voxel plus every neighbor whose mesh borders that voxel.
+pub fn chunks_sharing_voxel(
+    chunk_coord: ChunkCoord,
+    voxel_x: usize,
+    voxel_y: usize,
+    voxel_z: usize,
+) -> HashSet<ChunkCoord> {
+    let mut chunks = HashSet::from([chunk_coord]);
+    let last = CHUNK_VOXELS_SIZE - 1;
+
+    if voxel_x == 0 {
+        chunks.insert(chunk_coord.offset(-1, 0, 0));
+    }
+    if voxel_x == last {
+        chunks.insert(chunk_coord.offset(1, 0, 0));
+    }
+    if voxel_y == 0 {
+        chunks.insert(chunk_coord.offset(0, -1, 0));
+    }
+    if voxel_y == last {
+        chunks.insert(chunk_coord.offset(0, 1, 0));
+    }
+    if voxel_z == 0 {
+        chunks.insert(chunk_coord.offset(0, 0, -1));
+    }
+    if voxel_z == last {
+        chunks.insert(chunk_coord.offset(0, 0, 1));
+    }
+
+    chunks
+}
+
+fn toggle_debug_info_system(
+    mut debug_info_state: ResMut<DebugInfoState>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::F1) {
+        debug_info_state.enabled = !debug_info_state.enabled;
+    }
+}
+
+/// F2 and F3 shrink and grow the render distance, F4 and F5 the simulation distance.
+fn adjust_view_distance_system(
+    mut view: ResMut<ViewDistanceSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    let step = |shrink: KeyCode, grow: KeyCode| {
+        i32::from(keys.just_pressed(grow)) - i32::from(keys.just_pressed(shrink))
+    };
+    let render_step = step(KeyCode::F2, KeyCode::F3);
+    let simulation_step = step(KeyCode::F4, KeyCode::F5);
+    if render_step == 0 && simulation_step == 0 {
+        return;
+    }
+
+    view.render_distance_chunks =
+        (view.render_distance_chunks + render_step).clamp(1, MAX_VIEW_DISTANCE_CHUNKS);
+    view.simulation_distance_chunks =
+        (view.simulation_distance_chunks + simulation_step).clamp(1, MAX_VIEW_DISTANCE_CHUNKS);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::generator::WorldPreset;
     use crate::player::Inventory;
     use crate::save::SaveState;
+    use crate::terrain::TerrainSettings;
+    use bevy::ecs::schedule::{LogLevel, ScheduleBuildSettings};
+    use bevy::ecs::system::RunSystemOnce;
     use bevy::state::app::StatesPlugin;
 
     #[test]
-    fn initial_target_chunks_cover_expected_radius() {
-        let coords = initial_target_chunks(ChunkCoord::new(0, 0));
+    fn voxel_lookup_extends_below_world_origin() {
+        let position = Vec3::new(-0.01, -0.01, 0.5);
+        let coord = ChunkCoord::from_world_pos(position);
+        assert_eq!(coord, ChunkCoord::new(-1, -1, 0));
+
+        let cell = voxel_cell(position);
+        assert_eq!(cell, IVec3::new(-1, -1, 8));
+        assert_eq!(ChunkCoord::from_cell(cell), coord);
+
+        let mut world = World::default();
+        assert_eq!(world.locate_cell(cell), None);
+        world.chunks.insert(coord, Entity::PLACEHOLDER);
+        assert_eq!(
+            world.locate_cell(cell),
+            Some((coord, CHUNK_VOXELS_SIZE - 1, CHUNK_VOXELS_SIZE - 1, 8))
+        );
+    }
+
+    #[test]
+    fn voxel_world_reads_and_writes_across_chunk_borders() {
+        let blocks = BlockRegistry::default();
+        let stone = Voxel::new(blocks.id_by_name("stone").unwrap());
+
+        let mut app = App::new();
+        app.init_resource::<World>();
+        for coord in [ChunkCoord::new(-1, 0, 0), ChunkCoord::new(0, 0, 0)] {
+            let entity = app.world_mut().spawn(Chunk::new(coord)).id();
+            app.world_mut()
+                .resource_mut::<World>()
+                .chunks
+                .insert(coord, entity);
+        }
 
-        assert_eq!(coords.len(), 29);
-        assert!(coords.contains(&ChunkCoord::new(0, 0)));
-        assert!(coords.contains(&ChunkCoord::new(3, 0)));
-        assert!(coords.contains(&ChunkCoord::new(0, -3)));
-        assert!(!coords.contains(&ChunkCoord::new(3, 3)));
+        let (neighbors, region) = app
+            .world_mut()
+            .run_system_once(move |mut voxels: VoxelWorld| {
+                assert!(voxels.set(IVec3::new(-1, 3, 3), stone));
+                assert!(!voxels.set(IVec3::new(-33, 3, 3), stone));
+                assert_eq!(voxels.get(IVec3::new(-1, 3, 3)), Some(stone));
+                assert_eq!(
+                    voxels
+                        .chunk(ChunkCoord::new(-1, 0, 0))
+                        .and_then(|chunk| chunk.get_voxel(CHUNK_VOXELS_SIZE - 1, 3, 3)),
+                    Some(stone)
+                );
+
+                let neighbors = voxels
+                    .neighbors(IVec3::new(0, 3, 3))
+                    .filter(|(_, voxel)| *voxel == Some(stone))
+                    .map(|(cell, _)| cell)
+                    .collect::<Vec<_>>();
+                let region = voxels
+                    .region(IVec3::new(-2, 3, 3), IVec3::new(1, 3, 3))
+                    .filter(|(_, voxel)| *voxel == stone)
+                    .count();
+                (neighbors, region)
+            })
+            .unwrap();
+
+        assert_eq!(neighbors, vec![IVec3::new(-1, 3, 3)]);
+        assert_eq!(region, 1);
+    }
+
+    #[test]
+    fn voxel_readers_can_run_in_parallel() {
+        let mut app = App::new();
+        app.init_resource::<World>()
+            .edit_schedule(Update, |schedule| {
+                schedule.set_build_settings(ScheduleBuildSettings {
+                    ambiguity_detection: LogLevel::Error,
+                    ..default()
+                });
+            })
+            .add_systems(Update, (|_: VoxelReader| {}, |_: VoxelReader| {}));
+
+        // Building the schedule fails if the two systems' accesses conflict.
+        app.update();
+    }
+
+    #[test]
+    fn editing_a_voxel_only_invalidates_adjacent_chunks() {
+        assert_eq!(
+            chunks_sharing_voxel(ChunkCoord::new(0, 0, 0), 3, 5, 7),
+            HashSet::from([ChunkCoord::new(0, 0, 0)])
+        );
+
+        let mut app = App::new();
+        app.init_resource::<World>();
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let coord = ChunkCoord::new(x, y, z);
+                    let entity = app.world_mut().spawn(Chunk::new(coord)).id();
+                    app.world_mut()
+                        .resource_mut::<World>()
+                        .chunks
+                        .insert(coord, entity);
+                }
+            }
+        }
+
+        // The cell sits on the chunk's -X face and +Y face, away from the Z faces.
+        let cell = IVec3::new(0, CHUNK_VOXELS_SIZE as i32 - 1, 7);
+        app.world_mut()
+            .run_system_once(move |mut commands: Commands, world: Res<World>| {
+                mark_chunk_for_update(&mut commands, &world, cell);
+            })
+            .unwrap();
+
+        let mut rebuilt = app
+            .world_mut()
+            .query_filtered::<&Chunk, (With<NeedsRenderRefresh>, With<NeedsPhysicsRefresh>)>();
+        let rebuilt = rebuilt
+            .iter(app.world())
+            .map(|chunk| chunk.coord)
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            rebuilt,
+            HashSet::from([
+                ChunkCoord::new(0, 0, 0),
+                ChunkCoord::new(-1, 0, 0),
+                ChunkCoord::new(0, 1, 0),
+            ])
+        );
+    }
+
+    #[test]
+    fn initial_target_chunks_cover_expected_radius() {
+        let (min_height, _) = default_surface_bounds();
+        let coords = initial_target_chunks(ChunkCoord::new(0, 0, 0), min_height);
+
+        assert_eq!(coords.len(), 123);
+        assert!(coords.contains(&ChunkCoord::new(0, 0, 0)));
+        assert!(coords.contains(&ChunkCoord::new(3, 0, 0)));
+        assert!(coords.contains(&ChunkCoord::new(0, -3, 0)));
+        assert!(coords.contains(&ChunkCoord::new(0, 0, -3)));
+        assert!(!coords.contains(&ChunkCoord::new(3, 3, 0)));
+
+        let high_coords = initial_target_chunks(ChunkCoord::new(0, 10, 0), min_height);
+        assert!(high_coords.contains(&ChunkCoord::new(0, 1, 0)));
+        assert!(!high_coords.contains(&ChunkCoord::new(1, 1, 0)));
     }
 
     #[test]
@@ -630,6 +1219,7 @@ mod tests {
             .init_state::<AppState>()
             .init_resource::<SaveState>()
             .init_resource::<Inventory>()
+            .init_resource::<BlockRegistry>()
             .init_resource::<ButtonInput<KeyCode>>()
             .add_plugins(WorldPlugin);
 
@@ -639,13 +1229,36 @@ mod tests {
 
         app.update();
 
+        // Nothing is queued until the spawn search, which runs on the task pool, comes back.
+        {
+            let generation = app.world().resource::<InitialWorldGeneration>();
+            assert_eq!(generation.total_chunks, 0);
+            assert!(app.world().resource::<World>().queued_chunks.is_empty());
+
+            let search = generation.spawn_search.as_ref().unwrap();
+            while !search.is_finished() {
+                std::thread::yield_now();
+            }
+        }
+        app.update();
+
         let generation = app.world().resource::<InitialWorldGeneration>();
         let world = app.world().resource::<World>();
 
+        assert!(generation.spawn_search.is_none());
+        let spawn_position = generation.spawn_position.unwrap();
+        let expected = initial_target_chunks(
+            ChunkCoord::from_world_pos(spawn_position),
+            world.surface_bounds().0,
+        )
+        .len();
         assert!(generation.started);
-        assert_eq!(generation.total_chunks, 29);
-        assert_eq!(generation.target_chunks.len(), 29);
-        assert_eq!(generation.completed_chunks + world.pending_chunks.len(), 29);
+        assert_eq!(generation.total_chunks, expected);
+        assert_eq!(generation.target_chunks.len(), expected);
+        assert_eq!(
+            generation.completed_chunks + world.pending_chunks.len() + world.queued_chunks.len(),
+            expected
+        );
     }
 
     #[test]
@@ -656,6 +1269,7 @@ mod tests {
             .init_state::<AppState>()
             .init_resource::<SaveState>()
             .init_resource::<Inventory>()
+            .init_resource::<BlockRegistry>()
             .init_resource::<ButtonInput<KeyCode>>()
             .add_plugins(WorldPlugin);
 
@@ -670,8 +1284,10 @@ mod tests {
             generation.started = true;
             generation.total_chunks = 1;
             generation.completed_chunks = 1;
-            generation.target_chunks.insert(ChunkCoord::new(0, 0));
-            generation.spawn_position = Some(initial_player_spawn_position());
+            generation.target_chunks.insert(ChunkCoord::new(0, 0, 0));
+            generation.spawn_position =
+                Some(initial_player_spawn_position(default_surface_bounds().1));
+            generation.spawn_search = None;
         }
 
         app.update();
@@ -683,6 +1299,15 @@ mod tests {
         );
     }
 
+    /// Blocks until every chunk generation task has finished, so the next update collects them
+    /// however busy the task pool shared with other tests is.
+    fn wait_for_pending_chunks(app: &App) {
+        let world = app.world().resource::<World>();
+        while !world.pending_chunks.values().all(Task::is_finished) {
+            std::thread::yield_now();
+        }
+    }
+
     #[test]
     fn ingame_processes_pending_chunks() {
         let mut app = App::new();
@@ -691,10 +1316,11 @@ mod tests {
             .init_state::<AppState>()
             .init_resource::<SaveState>()
             .init_resource::<Inventory>()
+            .init_resource::<BlockRegistry>()
             .init_resource::<ButtonInput<KeyCode>>()
             .add_plugins(WorldPlugin);
 
-        let coord = ChunkCoord::new(0, 0);
+        let coord = ChunkCoord::new(0, 0, 0);
         {
             let mut world = app.world_mut().resource_mut::<World>();
             let task_pool = AsyncComputeTaskPool::get();
@@ -707,6 +1333,7 @@ mod tests {
             .resource_mut::<NextState<AppState>>()
             .set(AppState::InGame);
 
+        wait_for_pending_chunks(&app);
         for _ in 0..8 {
             app.update();
         }
@@ -716,6 +1343,145 @@ mod tests {
         assert!(!world.pending_chunks.contains_key(&coord));
     }
 
+    #[test]
+    fn generation_is_capped_starts_chunks_in_view_first_and_drops_far_ones() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(StatesPlugin)
+            .init_state::<AppState>()
+            .init_resource::<SaveState>()
+            .init_resource::<Inventory>()
+            .init_resource::<BlockRegistry>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .add_plugins(WorldPlugin);
+
+        let blocks = app.world().resource::<BlockRegistry>().clone();
+        app.world_mut().resource_mut::<World>().generator =
+            Some(WorldPreset::Void.generator(0, TerrainSettings::default(), &blocks));
+        let player = app.world_mut().spawn((Player, Transform::default())).id();
+        let forward = Vec3::X;
+        app.world_mut().spawn((
+            PlayerCamera,
+            GlobalTransform::from(Transform::default().looking_to(forward, Vec3::Y)),
+        ));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+        app.update();
+
+        let focus = ChunkCoord::new(0, 0, 0);
+        let world = app.world().resource::<World>();
+        let started = world.chunks.keys().chain(world.pending_chunks.keys());
+        assert_eq!(started.clone().count(), MAX_GENERATION_TASKS);
+        assert!(!world.queued_chunks.is_empty());
+        let last_started = started
+            .map(|coord| generation_priority(*coord, focus, forward))
+            .fold(0.0, f32::max);
+        let first_queued = world
+            .queued_chunks
+            .iter()
+            .map(|coord| generation_priority(*coord, focus, forward))
+            .fold(f32::MAX, f32::min);
+        assert!(last_started <= first_queued);
+        assert!(
+            generation_priority(ChunkCoord::new(2, 0, 0), focus, forward)
+                < generation_priority(ChunkCoord::new(-2, 0, 0), focus, forward)
+        );
+
+        app.world_mut()
+            .entity_mut(player)
+            .insert(Transform::from_xyz(200.0, 0.0, 0.0));
+        app.update();
+
+        let world = app.world().resource::<World>();
+        let focus = ChunkCoord::from_world_pos(Vec3::new(200.0, 0.0, 0.0));
+        let load_distance = ViewDistanceSettings::default().load_distance_chunks();
+        assert!(
+            world
+                .queued_chunks
+                .iter()
+                .chain(world.pending_chunks.keys())
+                .all(|coord| coord.distance_squared(focus) <= load_distance.pow(2))
+        );
+    }
+
+    #[test]
+    fn changing_view_distance_streams_chunks_in_and_out() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(StatesPlugin)
+            .init_state::<AppState>()
+            .init_resource::<SaveState>()
+            .init_resource::<Inventory>()
+            .init_resource::<BlockRegistry>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .add_plugins(WorldPlugin);
+
+        let blocks = app.world().resource::<BlockRegistry>().clone();
+        app.world_mut().resource_mut::<World>().generator =
+            Some(WorldPreset::Void.generator(0, TerrainSettings::default(), &blocks));
+        app.world_mut().spawn((Player, Transform::default()));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+
+        let center = ChunkCoord::new(0, 0, 0);
+        let settle = |app: &mut App, view: ViewDistanceSettings| {
+            *app.world_mut().resource_mut::<ViewDistanceSettings>() = view;
+            loop {
+                app.update();
+                let world = app.world().resource::<World>();
+                if world.pending_chunks.is_empty() && world.queued_chunks.is_empty() {
+                    break;
+                }
+                wait_for_pending_chunks(app);
+            }
+            app.world()
+                .resource::<World>()
+                .chunks
+                .keys()
+                .copied()
+                .collect::<HashSet<_>>()
+        };
+
+        let loaded = settle(
+            &mut app,
+            ViewDistanceSettings {
+                render_distance_chunks: 4,
+                simulation_distance_chunks: 1,
+            },
+        );
+        assert_eq!(loaded, chunks_within_radius(center, 4));
+        let world = app.world().resource::<World>();
+        assert_eq!(world.focus, Some(center));
+        let view = *app.world().resource::<ViewDistanceSettings>();
+        assert!(!world.simulation_paused(IVec3::ZERO, &view));
+        assert!(world.simulation_paused(IVec3::new(0, 0, 2 * CHUNK_VOXELS_SIZE as i32), &view));
+
+        // Shrinking the load distance keeps a margin of chunks around before unloading them.
+        let loaded = settle(
+            &mut app,
+            ViewDistanceSettings {
+                render_distance_chunks: 1,
+                simulation_distance_chunks: 1,
+            },
+        );
+        assert_eq!(
+            loaded,
+            chunks_within_radius(center, 1 + UNLOAD_MARGIN_CHUNKS)
+        );
+
+        // Chunks load out to the simulation distance even past the render distance.
+        let loaded = settle(
+            &mut app,
+            ViewDistanceSettings {
+                render_distance_chunks: 1,
+                simulation_distance_chunks: 5,
+            },
+        );
+        assert_eq!(loaded, chunks_within_radius(center, 5));
+    }
+
     #[test]
     fn resuming_from_pause_does_not_spawn_duplicate_player() {
         let mut app = App::new();
@@ -724,12 +1490,14 @@ mod tests {
             .init_state::<AppState>()
             .init_resource::<SaveState>()
             .init_resource::<Inventory>()
+            .init_resource::<BlockRegistry>()
             .init_resource::<ButtonInput<KeyCode>>()
             .add_plugins(WorldPlugin);
 
         {
             let mut generation = app.world_mut().resource_mut::<InitialWorldGeneration>();
-            generation.spawn_position = Some(initial_player_spawn_position());
+            generation.spawn_position =
+                Some(initial_player_spawn_position(default_surface_bounds().1));
         }
 
         app.world_mut()
@@ -755,39 +1523,3 @@ mod tests {
         assert_eq!(player_count, 1);
     }
 }
-
-pub fn mark_chunk_for_update(commands: &mut Commands, world: &World, world_pos: Vec3) {
-    if let Some((chunk_coord, voxel_x, _, voxel_z)) = world.world_to_voxel(world_pos) {
-        let mut dirty_chunks = bevy::platform::collections::HashSet::from([chunk_coord]);
-
-        if voxel_x == 0 {
-            dirty_chunks.insert(ChunkCoord::new(chunk_coord.x - 1, chunk_coord.z));
-        }
-        if voxel_x + 1 == CHUNK_VOXELS_SIZE {
-            dirty_chunks.insert(ChunkCoord::new(chunk_coord.x + 1, chunk_coord.z));
-        }
-        if voxel_z == 0 {
-            dirty_chunks.insert(ChunkCoord::new(chunk_coord.x, chunk_coord.z - 1));
-        }
-        if voxel_z + 1 == CHUNK_VOXELS_SIZE {
-            dirty_chunks.insert(ChunkCoord::new(chunk_coord.x, chunk_coord.z + 1));
-        }
-
-        for dirty_chunk in dirty_chunks {
-            if let Some(chunk_entity) = world.chunks.get(&dirty_chunk) {
-                commands
-                    .entity(*chunk_entity)
-                    .insert((NeedsRenderRefresh, NeedsPhysicsRefresh));
-            }
-        }
-    }
-}
-
-fn toggle_debug_info_system(
-    mut debug_info_state: ResMut<DebugInfoState>,
-    keys: Res<ButtonInput<KeyCode>>,
-) {
-    if keys.just_pressed(KeyCode::F1) {
-        debug_info_state.enabled = !debug_info_state.enabled;
-    }
-}